@@ -0,0 +1,170 @@
+use std::{process::ExitCode, time::Instant};
+
+use anyhow::{bail, ensure, Context, Result};
+use aoc_2021::{compile_bits, solutions, InputLocation, LengthType, Solution};
+
+const USAGE: &str = "Usage: solve --day <N> [--part <1|2>] [--variant <name|all>]
+             [--input <path|->] [--input-dir <dir>] [--user <name>]
+       solve --compile <expression> [--length-type <bits|count>]
+
+Without --variant the plain solution of each part runs, or its variants if it has
+no plain one. Input defaults to <dir>/[<user>/]day<N>.txt, where the directory is
+res unless AOC_INPUT_DIR is set and the user comes from AOC_USER. An --input of
+`-` reads stdin.
+
+--compile prints the day 16 transmission for an expression such as
+`max(3, 4*5) < sum(1,2,3)`, with operators using the given length type.";
+
+#[derive(Debug, Default)]
+struct Options {
+    day: u8,
+    part: Option<u8>,
+    variant: Option<String>,
+    input: Option<String>,
+    input_dir: Option<String>,
+    user: Option<String>,
+    compile: Option<String>,
+    length_type: LengthType,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options> {
+    let mut day = None;
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        }
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--day" => day = Some(value()?.parse().context("Bad day")?),
+            "--part" => options.part = Some(value()?.parse().context("Bad part")?),
+            "--variant" => options.variant = Some(value()?),
+            "--input" => options.input = Some(value()?),
+            "--input-dir" => options.input_dir = Some(value()?),
+            "--user" => options.user = Some(value()?),
+            "--compile" => options.compile = Some(value()?),
+            "--length-type" => options.length_type = value()?.parse()?,
+            _ => bail!("Unknown argument {}\n\n{}", arg, USAGE),
+        }
+    }
+    if options.compile.is_none() {
+        options.day = day.with_context(|| format!("--day is required\n\n{}", USAGE))?;
+    }
+    ensure!(
+        options.part.is_none_or(|p| p == 1 || p == 2),
+        "Part must be 1 or 2"
+    );
+    Ok(options)
+}
+
+fn location(options: &Options) -> InputLocation {
+    let mut result = InputLocation::from_env();
+    if let Some(dir) = &options.input_dir {
+        result = result.with_dir(dir);
+    }
+    if let Some(user) = &options.user {
+        result = result.with_user(user);
+    }
+    result
+}
+
+fn load_input(options: &Options) -> Result<String> {
+    match options.input.as_deref() {
+        // Explicit paths are relative to the working directory, not the input directory
+        Some(path) => InputLocation::new(".", None).read(path),
+        None => location(options).read_day(options.day),
+    }
+}
+
+fn select(options: &Options) -> Result<Vec<Solution>> {
+    let mut result = vec![];
+    for part in 1..=2 {
+        if options.part.is_some_and(|p| p != part) {
+            continue;
+        }
+        let candidates: Vec<Solution> = solutions()
+            .into_iter()
+            .filter(|s| s.day == options.day && s.part == part)
+            .collect();
+        match options.variant.as_deref() {
+            Some("all") => result.extend(candidates),
+            Some(variant) => result.extend(
+                candidates
+                    .into_iter()
+                    .filter(|s| s.variant == Some(variant)),
+            ),
+            None if candidates.iter().any(|s| s.variant.is_none()) => {
+                result.extend(candidates.into_iter().filter(|s| s.variant.is_none()))
+            }
+            None => result.extend(candidates),
+        }
+    }
+    ensure!(
+        !result.is_empty(),
+        "No solution for day {}{}{}",
+        options.day,
+        options
+            .part
+            .map(|p| format!(" part {}", p))
+            .unwrap_or_default(),
+        options
+            .variant
+            .as_ref()
+            .map(|v| format!(" variant {}", v))
+            .unwrap_or_default()
+    );
+    Ok(result)
+}
+
+/// Runs one solution, printing its answer and timings like cargo-aoc does.
+fn solve(solution: &Solution, input: &str) -> Result<()> {
+    let start = Instant::now();
+    let runner = solution.generate(input)?;
+    let generated = Instant::now();
+    let answer = runner
+        .try_run()
+        .map_err(|e| anyhow::anyhow!("Solver failed: {}", e))?;
+    let finished = Instant::now();
+    println!(
+        "{}: {}\n\tgenerator: {:?},\n\trunner: {:?}\n",
+        solution,
+        answer,
+        generated - start,
+        finished - generated
+    );
+    Ok(())
+}
+
+fn run() -> Result<bool> {
+    let options = parse_args(std::env::args().skip(1))?;
+    if let Some(source) = &options.compile {
+        println!("{}", compile_bits(source, options.length_type)?);
+        return Ok(true);
+    }
+    let solutions = select(&options)?;
+    let input = load_input(&options)?;
+
+    let mut ok = true;
+    for solution in &solutions {
+        if let Err(e) = solve(solution, &input) {
+            eprintln!("{}: FAILED: {:#}\n", solution, e);
+            ok = false;
+        }
+    }
+    Ok(ok)
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -1,47 +1,29 @@
 use std::collections::HashSet;
 
 use anyhow::Result;
-use itertools::iproduct;
 
-use crate::MatrixTranspose;
+use crate::Grid;
 
 #[aoc_generator(day11)]
-fn input_generator(input: &str) -> Result<Vec<Vec<i32>>> {
-    let result: Vec<Vec<i32>> = input
-        .lines()
-        .map(|l| {
-            l.chars()
-                .map(|c| c as u8 - b'0')
-                .map(|b| b as i32)
-                .collect()
-        })
-        .collect();
-    let result: Vec<Vec<i32>> = result.transpose();
-    Ok(result)
+fn input_generator(input: &str) -> Result<Grid<i32>> {
+    Ok(Grid::parse_digits(input)?.map(|d| *d as i32))
 }
 
-#[allow(dead_code, clippy::needless_range_loop)]
-fn print_octopuses(input: &[Vec<i32>]) {
-    for y in 0..10 {
-        for x in 0..10 {
-            print!("{}", input[x][y]);
-        }
-        println!();
-    }
-    println!();
+#[allow(dead_code)]
+fn print_octopuses(input: &Grid<i32>) {
+    println!("{}", input);
 }
 
-fn one_round(input: &mut [Vec<i32>]) -> i32 {
+fn one_round(input: &mut Grid<i32>) -> i32 {
     let mut flashed: HashSet<(usize, usize)> = HashSet::new();
     let mut queue = vec![];
 
     // Initial increase
-    for (y, x) in iproduct!(0..10, 0..10) {
-        input[x][y] += 1;
-        let octopus = input[x][y];
-        if octopus == 10 {
-            queue.push((x, y));
-            flashed.insert((x, y));
+    for (coord, octopus) in input.coords().zip(input.values_mut()) {
+        *octopus += 1;
+        if *octopus == 10 {
+            queue.push(coord);
+            flashed.insert(coord);
         }
     }
     // println!("Post increment");
@@ -49,36 +31,25 @@ fn one_round(input: &mut [Vec<i32>]) -> i32 {
     while let Some(octopus_coord) = queue.pop() {
         // println!("\tFlashing: {:?}", octopus_coord);
         // This hasn't flashed before
-        for (y_off, x_off) in iproduct!(-1i32..=1, -1i32..=1) {
-            if x_off == 0 && y_off == 0 {
-                continue;
-            }
-            let x = octopus_coord.0 as i32 + x_off;
-            let y = octopus_coord.1 as i32 + y_off;
-            if !(0..10).contains(&x) || !(0..10).contains(&y) {
-                continue;
-            }
-            let x = x as usize;
-            let y = y as usize;
-
-            input[x][y] += 1;
-            let octopus = input[x][y];
-            // println!("Incrementing: {:?} -> {}", (x, y), octopus);
-            if octopus == 10 && flashed.insert((x, y)) {
-                // println!("\t\tAdded flash: {:?}", (x, y));
-                queue.push((x, y));
-                flashed.insert((x, y));
+        let neighbors: Vec<_> = input.neighbors8(octopus_coord).collect();
+        for coord in neighbors {
+            input[coord] += 1;
+            let octopus = input[coord];
+            // println!("Incrementing: {:?} -> {}", coord, octopus);
+            if octopus == 10 && flashed.insert(coord) {
+                // println!("\t\tAdded flash: {:?}", coord);
+                queue.push(coord);
             }
         }
     }
 
     for octopus_coord in &flashed {
-        input[octopus_coord.0][octopus_coord.1] = 0;
+        input[*octopus_coord] = 0;
     }
     flashed.len() as i32
 }
 
-fn count_flashes(input: &[Vec<i32>], limit: usize) -> Result<i32> {
+fn count_flashes(input: &Grid<i32>, limit: usize) -> Result<i32> {
     let mut input = input.to_owned();
     let mut result = 0;
 
@@ -91,16 +62,16 @@ fn count_flashes(input: &[Vec<i32>], limit: usize) -> Result<i32> {
 }
 
 #[aoc(day11, part1)]
-fn part1(input: &[Vec<i32>]) -> Result<i32> {
+fn part1(input: &Grid<i32>) -> Result<i32> {
     count_flashes(input, 100)
 }
 
 #[aoc(day11, part2)]
-fn part2(input: &[Vec<i32>]) -> Result<i32> {
+fn part2(input: &Grid<i32>) -> Result<i32> {
     let mut input = input.to_owned();
     let mut round = 1;
     loop {
-        if one_round(&mut input) == 100 {
+        if one_round(&mut input) == input.len() as i32 {
             return Ok(round);
         }
         round += 1;
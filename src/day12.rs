@@ -1,6 +1,6 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
-use anyhow::Result;
+use anyhow::{ensure, Context, Result};
 
 use crate::Graph;
 
@@ -10,118 +10,206 @@ fn is_large(name: &str) -> bool {
 
 #[aoc_generator(day12)]
 fn input_generator(input: &str) -> Result<Graph<String>> {
-    let mut cavern = Graph::new(true);
-    for (cave1, cave2) in input.lines().flat_map(|l| l.split_once("-")) {
-        cavern.add_edge(cave1.to_string(), cave2.to_string());
-    }
-
+    let mut cavern = Graph::parse_edges(input)?;
+    ensure!(!cavern.is_directed(), "Cave connections go both ways");
     cavern.sort();
     Ok(cavern)
 }
 
-fn enum_paths(
-    start: &str,
-    cavern: &Graph<String>,
-    visited_small: &mut HashSet<String>,
-    depth: usize,
-) -> Vec<Vec<String>> {
-    // for _ in 0..depth {
-    //     print!(" ");
-    // }
-    // println!("+Start {}", start);
-    if start == "end" {
-        return vec![vec!["end".to_string()]];
+/// How often small caves may be entered. Up to `caves` different small caves
+/// may be visited `visits` times each, every other one only once. The start
+/// cave is never re-entered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RevisitPolicy {
+    caves: usize,
+    visits: u8,
+}
+
+impl RevisitPolicy {
+    fn new(caves: usize, visits: u8) -> Result<Self> {
+        ensure!(
+            (1..=0xf).contains(&visits),
+            "Visits must be between 1 and 15, not {}",
+            visits
+        );
+        Ok(Self { caves, visits })
     }
-    let mut result = vec![];
-    for next in cavern.edges(start).unwrap() {
-        // for _ in 0..depth {
-        //     print!(" ");
-        // }
-        // println!("=Trying {}", next);
-        if is_large(next) || visited_small.insert(next.to_string()) {
-            for mut route in enum_paths(next, cavern, visited_small, depth + 1) {
-                route.insert(0, start.to_string());
-                // for _ in 0..depth {
-                //     print!(" ");
-                // }
-                // println!("-Found route: {:?}", route);
-                result.push(route);
+}
+
+/// Visit counts for every small cave, packed four bits per cave.
+type Visits = u128;
+const VISIT_BITS: usize = 4;
+const MAX_SMALL_CAVES: usize = Visits::BITS as usize / VISIT_BITS;
+
+/// The cavern with cave names interned to indexes.
+struct Caves {
+    names: Vec<String>,
+    edges: Vec<Vec<usize>>,
+    /// Which slot in [`Visits`] tracks each small cave.
+    small: Vec<Option<usize>>,
+    start: usize,
+    end: usize,
+}
+
+impl Caves {
+    fn new(cavern: &Graph<String>) -> Result<Self> {
+        let mut names: Vec<String> = cavern.nodes().cloned().collect();
+        names.sort();
+        let index: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        let start = *index.get("start").context("No start cave")?;
+        let end = *index.get("end").context("No end cave")?;
+
+        let mut small = vec![];
+        let mut slots = 0;
+        for name in &names {
+            if is_large(name) {
+                small.push(None);
+            } else {
+                small.push(Some(slots));
+                slots += 1;
+            }
+        }
+        ensure!(slots <= MAX_SMALL_CAVES, "Too many small caves: {}", slots);
+
+        let mut edges = vec![];
+        for name in &names {
+            let mut next = vec![];
+            for dest in cavern.edges(name).into_iter().flatten() {
+                ensure!(
+                    !(is_large(name) && is_large(dest)),
+                    "Large caves {} and {} connect, so there are endless paths",
+                    name,
+                    dest
+                );
+                next.push(index[dest.as_str()]);
             }
-            visited_small.remove(next);
+            edges.push(next);
         }
+
+        Ok(Self {
+            names,
+            edges,
+            small,
+            start,
+            end,
+        })
+    }
+
+    /// The visits after entering `cave`, or `None` if the policy forbids it.
+    fn enter(&self, cave: usize, visits: Visits, policy: &RevisitPolicy) -> Option<Visits> {
+        if cave == self.start {
+            return None;
+        }
+        let slot = match self.small[cave] {
+            Some(slot) => slot * VISIT_BITS,
+            None => return Some(visits),
+        };
+        let count = (visits >> slot) & 0xf;
+        if count > 0 {
+            if count >= policy.visits as Visits {
+                return None;
+            }
+            if count == 1 && revisited(visits) >= policy.caves {
+                return None;
+            }
+        }
+        Some(visits + (1 << slot))
     }
-    result
-}
 
-fn enum_paths2(
-    start: &str,
-    cavern: &Graph<String>,
-    visited_small: &mut HashMap<String, i32>,
-    depth: usize,
-) -> Vec<Vec<String>> {
-    // for _ in 0..depth {
-    //     print!(" ");
-    // }
-    // println!("+Start {}", start);
-    if start == "end" {
-        return vec![vec!["end".to_string()]];
+    /// Counts the paths to the end without building them, memoized on the
+    /// current cave and the visit counts so far.
+    fn count(&self, policy: &RevisitPolicy) -> u64 {
+        let mut memo = HashMap::new();
+        self.count_from(self.start, 0, policy, &mut memo)
     }
-    let mut result = vec![];
-    for next in cavern.edges(start).unwrap() {
-        if next == "start" {
-            continue;
+
+    fn count_from(
+        &self,
+        cave: usize,
+        visits: Visits,
+        policy: &RevisitPolicy,
+        memo: &mut HashMap<(usize, Visits), u64>,
+    ) -> u64 {
+        if cave == self.end {
+            return 1;
+        }
+        if let Some(count) = memo.get(&(cave, visits)) {
+            return *count;
         }
-        // for _ in 0..depth {
-        //     print!(" ");
-        // }
-        // println!("=Trying {}", next);
-        if is_large(next) || can_fit(next, visited_small) {
-            for mut route in enum_paths2(next, cavern, visited_small, depth + 1) {
-                route.insert(0, start.to_string());
-                // for _ in 0..depth {
-                //     print!(" ");
-                // }
-                // println!("-Found route: {:?}", route);
-                result.push(route);
+        let mut result = 0;
+        for next in &self.edges[cave] {
+            if let Some(visits) = self.enter(*next, visits, policy) {
+                result += self.count_from(*next, visits, policy, memo);
             }
-            if let Some(v) = visited_small.get_mut(next) {
-                *v -= 1;
+        }
+        memo.insert((cave, visits), result);
+        result
+    }
+
+    /// Every path from start to end allowed by `policy`.
+    fn routes(&self, policy: &RevisitPolicy) -> Vec<Vec<String>> {
+        let mut result = vec![];
+        let mut route = vec![self.start];
+        self.routes_from(0, policy, &mut route, &mut result);
+        result
+    }
+
+    fn routes_from(
+        &self,
+        visits: Visits,
+        policy: &RevisitPolicy,
+        route: &mut Vec<usize>,
+        result: &mut Vec<Vec<String>>,
+    ) {
+        let cave = *route.last().unwrap();
+        if cave == self.end {
+            result.push(route.iter().map(|c| self.names[*c].clone()).collect());
+            return;
+        }
+        for next in &self.edges[cave] {
+            if let Some(visits) = self.enter(*next, visits, policy) {
+                route.push(*next);
+                self.routes_from(visits, policy, route, result);
+                route.pop();
             }
         }
     }
-    result
 }
 
-fn can_fit(name: &str, visited_small: &mut HashMap<String, i32>) -> bool {
-    let revisit_banned = visited_small.values().any(|v| *v >= 2);
-    // println!("Values: {:?}", visited_small.values());
-    let entry = visited_small.entry(name.to_string()).or_default();
-    let result = *entry < if revisit_banned { 1 } else { 2 };
-    if result {
-        *entry += 1;
+/// How many small caves have been visited more than once.
+fn revisited(mut visits: Visits) -> usize {
+    let mut result = 0;
+    while visits != 0 {
+        if visits & 0xf > 1 {
+            result += 1;
+        }
+        visits >>= VISIT_BITS;
     }
-    // println!("\t{}({})->{}", name, revisit_banned, entry);
     result
 }
 
 #[aoc(day12, part1)]
-fn part1(input: &Graph<String>) -> Result<usize> {
-    let mut visited_small = HashSet::new();
-    visited_small.insert("start".to_string());
-    let paths = enum_paths("start", input, &mut visited_small, 0);
-
-    // println!("Paths: {:?}", paths);
-    Ok(paths.len())
+fn part1(input: &Graph<String>) -> Result<u64> {
+    Ok(Caves::new(input)?.count(&RevisitPolicy::new(0, 1)?))
 }
 
 #[aoc(day12, part2)]
-fn part2(input: &Graph<String>) -> Result<usize> {
-    let mut visited_small = HashMap::new();
-    // visited_small.insert("start".to_string(), 2);
-    let paths = enum_paths2("start", input, &mut visited_small, 0);
+fn part2(input: &Graph<String>) -> Result<u64> {
+    Ok(Caves::new(input)?.count(&RevisitPolicy::new(1, 2)?))
+}
 
-    // println!("Paths: {:?}", paths);
-    Ok(paths.len())
+#[aoc(day12, part1, enumerate)]
+fn part1_enumerate(input: &Graph<String>) -> Result<usize> {
+    Ok(Caves::new(input)?.routes(&RevisitPolicy::new(0, 1)?).len())
+}
+
+#[aoc(day12, part2, enumerate)]
+fn part2_enumerate(input: &Graph<String>) -> Result<usize> {
+    Ok(Caves::new(input)?.routes(&RevisitPolicy::new(1, 2)?).len())
 }
 
 #[cfg(test)]
@@ -136,11 +224,90 @@ b-d
 A-end
 b-end";
 
+    const SMOKE_MEDIUM: &str = "dc-end
+HN-start
+start-kj
+dc-start
+dc-HN
+LN-dc
+HN-end
+kj-sa
+kj-HN
+kj-dc";
+
+    const SMOKE_LARGE: &str = "fs-end
+he-DX
+fs-he
+start-DX
+pj-DX
+end-zg
+zg-sl
+zg-pj
+pj-he
+RW-he
+fs-DX
+pj-RW
+zg-RW
+start-pj
+he-WI
+zg-he
+pj-fs
+start-RW";
+
     #[test]
     fn smoke1() -> Result<()> {
         let input = input_generator(SMOKE)?;
         println!("Graph:\n{}", input);
         assert_eq!(10, part1(&input)?);
+        assert_eq!(10, part1_enumerate(&input)?);
+        assert_eq!(19, part1(&input_generator(SMOKE_MEDIUM)?)?);
+        assert_eq!(226, part1(&input_generator(SMOKE_LARGE)?)?);
+        Ok(())
+    }
+
+    #[test]
+    fn policy() -> Result<()> {
+        let caves = Caves::new(&input_generator(SMOKE_MEDIUM)?)?;
+        for (revisits, visits) in [(0, 1), (1, 2), (2, 2), (1, 3), (3, 3)] {
+            let policy = RevisitPolicy::new(revisits, visits)?;
+            let routes = caves.routes(&policy);
+            assert_eq!(routes.len() as u64, caves.count(&policy));
+        }
+        assert!(RevisitPolicy::new(1, 0).is_err());
+        assert!(RevisitPolicy::new(1, 16).is_err());
+        assert!(Caves::new(&input_generator("start-A\nA-B\nB-end")?).is_err());
+        assert!(Caves::new(&input_generator("start-a")?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn dot() -> Result<()> {
+        let input = input_generator(SMOKE)?;
+        let route = Caves::new(&input)?
+            .routes(&RevisitPolicy::new(0, 1)?)
+            .into_iter()
+            .next()
+            .unwrap();
+        assert_eq!("start,A,b,A,c,A,end", route.join(","));
+        assert_eq!(
+            r#"graph {
+    "A" [color=red];
+    "b" [color=red];
+    "c" [color=red];
+    "d";
+    "end" [color=red];
+    "start" [color=red];
+    "A" -- "b" [color=red, penwidth=2];
+    "A" -- "c" [color=red, penwidth=2];
+    "A" -- "end" [color=red, penwidth=2];
+    "A" -- "start" [color=red, penwidth=2];
+    "b" -- "d";
+    "b" -- "end";
+    "b" -- "start";
+}
+"#,
+            input.to_dot(Some(&route))
+        );
         Ok(())
     }
 
@@ -148,6 +315,9 @@ b-end";
     fn smoke2() -> Result<()> {
         let input = input_generator(SMOKE)?;
         assert_eq!(36, part2(&input)?);
+        assert_eq!(36, part2_enumerate(&input)?);
+        assert_eq!(103, part2(&input_generator(SMOKE_MEDIUM)?)?);
+        assert_eq!(3509, part2(&input_generator(SMOKE_LARGE)?)?);
         Ok(())
     }
 }
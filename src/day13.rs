@@ -1,27 +1,29 @@
-use std::collections::HashSet;
-
 use anyhow::{bail, Context, Result};
 use regex::Regex;
 
-type Coord = (usize, usize);
+use crate::{Axis, Bitmap};
 
 struct Input {
-    dots: HashSet<Coord>,
-    folds: Vec<(String, usize)>,
+    dots: Bitmap,
+    folds: Vec<(Axis, i32)>,
 }
 
 #[aoc_generator(day13)]
 fn input_generator(input: &str) -> Result<Input> {
-    let mut dots = HashSet::new();
+    let mut dots = Bitmap::new(false);
     let mut folds = vec![];
 
     let re = Regex::new("fold along (.)=(\\d+)")?;
     for l in input.lines() {
-        if let Some((x, y)) = l.split_once(",") {
-            dots.insert((x.parse()?, y.parse()?));
+        if let Some((x, y)) = l.split_once(',') {
+            dots.set((x.parse()?, y.parse()?), true);
         } else if !l.is_empty() {
             let mat = re.captures(l).context("no match1")?;
-            let dir = mat.get(1).context("No match2")?.as_str().to_string();
+            let dir = match mat.get(1).context("No match2")?.as_str() {
+                "x" => Axis::X,
+                "y" => Axis::Y,
+                _ => bail!("Bad direction"),
+            };
             let axis = mat.get(2).context("No match3")?.as_str().parse()?;
             folds.push((dir, axis));
         }
@@ -29,72 +31,37 @@ fn input_generator(input: &str) -> Result<Input> {
     Ok(Input { dots, folds })
 }
 
-fn fold(dots: &HashSet<Coord>, fold: &(String, usize)) -> Result<HashSet<Coord>> {
-    let mut result = HashSet::new();
+fn fold(dots: &Bitmap, fold: &(Axis, i32)) -> Bitmap {
     println!("Folding {:?}", fold);
-    for dot in dots {
-        match fold.0.as_str() {
-            "y" => {
-                if dot.1 < fold.1 {
-                    result.insert(*dot);
-                } else {
-                    result.insert((dot.0, fold.1 + fold.1 - dot.1));
-                }
-            }
-            "x" => {
-                if dot.0 < fold.1 {
-                    result.insert(*dot);
-                } else {
-                    result.insert((fold.1 + fold.1 - dot.0, dot.1));
-                }
-            }
-            _ => bail!("Bad direction"),
-        }
-    }
-    Ok(result)
+    dots.fold(fold.0, fold.1)
 }
 
 #[allow(dead_code)]
-fn print_dots(dots: &HashSet<Coord>) {
-    let (max_x, max_y) = dots
-        .iter()
-        .copied()
-        .reduce(|old, new| (old.0.max(new.0), (old.1.max(new.1))))
-        .unwrap();
-
-    for y in 0..=max_y {
-        for x in 0..=max_x {
-            if dots.contains(&(x, y)) {
-                print!("#");
-            } else {
-                print!(".");
-            }
-        }
-        println!();
-    }
-    println!();
+fn print_dots(dots: &Bitmap) {
+    println!("{}", dots);
 }
 
 #[aoc(day13, part1)]
 fn part1(input: &Input) -> Result<usize> {
     // print_dots(&input.dots);
     // println!();
-    println!("Dot count: {}", input.dots.len());
-    let result = fold(&input.dots, &input.folds[0])?;
-    println!("Dot count: {}", result.len());
+    println!("Dot count: {:?}", input.dots.lit_count());
+    let result = fold(&input.dots, &input.folds[0]);
+    let count = result.lit_count().context("Infinite dots")?;
+    println!("Dot count: {}", count);
 
     // print_dots(&result);
     // let result = fold(&result, &input.folds[1])?;
     // print_dots(&result);
 
-    Ok(result.len())
+    Ok(count)
 }
 
 #[aoc(day13, part2)]
 fn part2(input: &Input) -> Result<usize> {
     let mut result = input.dots.clone();
     for f in &input.folds {
-        result = fold(&result, f)?;
+        result = fold(&result, f);
     }
     print_dots(&result);
     Ok(1)
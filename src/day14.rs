@@ -1,10 +1,10 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Sub};
 
 use anyhow::{bail, Context, Result};
 use itertools::{Itertools, MinMaxResult};
 use regex::Regex;
 
-use crate::ReplacementEngine;
+use crate::{Count, ReplacementEngine};
 
 type Input = (String, HashMap<String, String>);
 
@@ -137,9 +137,9 @@ fn part2(input: &Input) -> Result<usize> {
 }
 
 #[allow(clippy::type_complexity)]
-fn build_replacement_rules(
+fn build_replacement_rules<C: Count>(
     rules: &HashMap<String, String>,
-) -> HashMap<(char, char), Vec<((char, char), u64)>> {
+) -> HashMap<(char, char), Vec<((char, char), C)>> {
     let mut result = HashMap::new();
 
     for (key, value) in rules {
@@ -147,42 +147,43 @@ fn build_replacement_rules(
         let value = value.chars().next().unwrap();
         let left = (key[0], value);
         let right = (value, key[1]);
-        result.insert((key[0], key[1]), vec![(left, 1), (right, 1)]);
+        result.insert((key[0], key[1]), vec![(left, C::one()), (right, C::one())]);
     }
     result
 }
 
-fn engine_iteration(input: &Input, steps: usize) -> Result<u64> {
-    let pairs = str_to_pairs(&input.0)
-        .iter()
-        .map(|(k, v)| (*k, *v as u64))
-        .collect();
+fn engine_iteration<C>(input: &Input, steps: usize) -> Result<C>
+where
+    C: Count + Ord + Sub<Output = C>,
+{
+    let chars: Vec<char> = input.0.chars().collect();
+    let mut pairs = HashMap::new();
+    for w in chars.windows(2) {
+        let count = pairs.entry((w[0], w[1])).or_insert_with(C::zero);
+        *count = count.checked_add(&C::one()).context("Count overflow")?;
+    }
 
     let rules = build_replacement_rules(&input.1);
 
     let mut engine = ReplacementEngine::new(pairs, rules);
-    for _step in 1..=steps {
-        engine.step();
-    }
+    engine.advance(steps as u64)?;
 
     let mut counts = HashMap::new();
     for (p, c) in &engine.elements {
         // The first elements are always the second element of something except the very first which we special case
-        *counts.entry(p.1).or_default() += c;
+        let count = counts.entry(p.1).or_insert_with(C::zero);
+        *count = count.checked_add(c).context("Count overflow")?;
     }
 
-    let mut template = input.0.chars();
-    let first = template.next().unwrap();
-    // let last = template.last().unwrap();
-    *counts.entry(first).or_default() += 1;
+    let first = *chars.first().context("Empty template")?;
+    let count = counts.entry(first).or_insert_with(C::zero);
+    *count = count.checked_add(&C::one()).context("Count overflow")?;
 
     // Avoid double counting first and last element. All others show up twice
 
-    let extremes = counts
-        .iter()
-        .minmax_by(|a: &(&char, &u64), b: &(&char, &u64)| a.1.cmp(b.1));
+    let extremes = counts.into_values().minmax();
     if let MinMaxResult::MinMax(min, max) = extremes {
-        Ok(max.1 - min.1)
+        Ok(max - min)
     } else {
         bail!("No min/max");
     }
@@ -200,7 +201,10 @@ fn part2_engine(input: &Input) -> Result<u64> {
 
 #[cfg(test)]
 mod tests {
+    use num_bigint::BigUint;
+
     use super::*;
+    use crate::differential::{check_agreement, solver, without_each, Rng};
 
     const SMOKE: &str = "NNCB
 
@@ -248,4 +252,62 @@ CN -> C";
         assert_eq!(2188189693529, part2(&input)?);
         Ok(())
     }
+
+    #[test]
+    fn long_run() -> Result<()> {
+        let input = input_generator(SMOKE)?;
+        assert!(engine_iteration::<u64>(&input, 100).is_err());
+        let exact: u128 = engine_iteration(&input, 100)?;
+        let big: BigUint = engine_iteration(&input, 100)?;
+        assert_eq!(exact.to_string(), big.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn variants_agree() {
+        const ELEMENTS: [char; 4] = ['B', 'C', 'H', 'N'];
+        let generate = |rng: &mut Rng| {
+            let template: Vec<char> = (0..rng.range(2, 8)).map(|_| rng.pick(&ELEMENTS)).collect();
+            let rules: Vec<char> = (0..16).map(|_| rng.pick(&ELEMENTS)).collect();
+            (template, rules)
+        };
+        let shrink = |(template, rules): &(Vec<char>, Vec<char>)| {
+            without_each(template)
+                .into_iter()
+                .filter(|t| t.len() >= 2)
+                .map(|t| (t, rules.clone()))
+                .collect()
+        };
+        let render = |(template, rules): &(Vec<char>, Vec<char>)| {
+            let mut result: String = template.iter().collect();
+            result.push('\n');
+            for (i, insert) in rules.iter().enumerate() {
+                result.push_str(&format!(
+                    "\n{}{} -> {}",
+                    ELEMENTS[i / 4],
+                    ELEMENTS[i % 4],
+                    insert
+                ));
+            }
+            result
+        };
+        let part1_solvers = [
+            solver("part1", &|s| Ok(part1(&input_generator(s)?)?.to_string())),
+            solver("part1_pairs", &|s| {
+                Ok(part1_pairs(&input_generator(s)?)?.to_string())
+            }),
+            solver("part1_engine", &|s| {
+                Ok(part1_engine(&input_generator(s)?)?.to_string())
+            }),
+        ];
+        let part2_solvers = [
+            solver("part2", &|s| Ok(part2(&input_generator(s)?)?.to_string())),
+            solver("part2_engine", &|s| {
+                Ok(part2_engine(&input_generator(s)?)?.to_string())
+            }),
+        ];
+        for solvers in [&part1_solvers[..], &part2_solvers[..]] {
+            check_agreement(30, generate, shrink, render, solvers);
+        }
+    }
 }
@@ -1,25 +1,19 @@
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::collections::{HashMap, HashSet};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use itertools::iproduct;
 
-use crate::MatrixTranspose;
+use crate::{dijkstra, Graph, Grid};
 
 type Coord = (usize, usize);
 
 #[aoc_generator(day15)]
-fn input_generator(input: &str) -> Result<Vec<Vec<i64>>> {
-    let map: Vec<Vec<i64>> = input
-        .lines()
-        .map(|l| l.as_bytes().iter().map(|b| (*b - b'0') as i64).collect())
-        .collect();
-    let map = map.transpose();
-    Ok(map)
+fn input_generator(input: &str) -> Result<Grid<i64>> {
+    Ok(Grid::parse_digits(input)?.map(|d| *d as i64))
 }
 
-fn calc_dist(map: &[Vec<i64>]) -> i64 {
-    let max_x = map.len() - 1;
-    let max_y = map[0].len() - 1;
+fn calc_dist(map: &Grid<i64>) -> i64 {
+    let end = (map.width() - 1, map.height() - 1);
     let mut distences: HashMap<Coord, i64> = HashMap::new();
     let mut visited: HashSet<Coord> = HashSet::new();
     distences.insert((0, 0), 0);
@@ -31,173 +25,87 @@ fn calc_dist(map: &[Vec<i64>]) -> i64 {
             .unwrap();
         let position = position.to_owned();
         let base_dist = base_dist.to_owned();
-        if position == (max_x, max_y) {
+        if position == end {
             return base_dist;
         }
-        let x = position.0;
-        let y = position.1;
 
-        if x > 0 {
-            let step = map[x - 1][y];
-            let current = distences.entry((x - 1, y)).or_insert(i64::MAX);
+        for next in map.neighbors4(position) {
+            let step = map[next];
+            let current = distences.entry(next).or_insert(i64::MAX);
             *current = (base_dist + step).min(*current);
         }
-        if y > 0 {
-            let step = map[x][y - 1];
-            let current = distences.entry((x, y - 1)).or_insert(i64::MAX);
-            *current = (base_dist + step).min(*current);
-        }
-        if x < max_x {
-            let step = map[x + 1][y];
-            let current = distences.entry((x + 1, y)).or_insert(i64::MAX);
-            *current = (base_dist + step).min(*current);
-        }
-        if y < max_y {
-            let step = map[x][y + 1];
-            let current = distences.entry((x, y + 1)).or_insert(i64::MAX);
-            *current = (base_dist + step).min(*current);
-        }
-        visited.insert((x, y));
+        visited.insert(position);
     }
 
     panic!("Should never reach here");
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-struct HeapElement {
-    position: Coord,
-    distance: i64,
+fn calc_dist2(map: &Grid<i64>) -> i64 {
+    let end = (map.width() - 1, map.height() - 1);
+    let neighbors = |position: &Coord| {
+        map.neighbors4(*position)
+            .map(|next| (next, map[next]))
+            .collect::<Vec<_>>()
+    };
+    dijkstra((0, 0), neighbors, |p| *p == end)
+        .expect("Should never reach here")
+        .cost
 }
 
-impl HeapElement {
-    fn new(x: usize, y: usize, distance: i64) -> Self {
-        let position = (x, y);
-        Self { position, distance }
-    }
-}
-
-impl PartialOrd for HeapElement {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for HeapElement {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other
-            .distance
-            .cmp(&self.distance)
-            .then(other.position.0.cmp(&self.position.0))
-            .then(other.position.1.cmp(&self.position.1))
-    }
-}
-
-fn calc_dist2(map: &[Vec<i64>]) -> i64 {
-    let max_x = map.len() - 1;
-    let max_y = map[0].len() - 1;
-    let mut distances = vec![vec![i64::MAX; max_y + 1]; max_x + 1];
-    let mut queue: BinaryHeap<HeapElement> = BinaryHeap::new();
-    // let mut distences: HashMap<Coord, i64> = HashMap::new();
-    let mut visited: HashSet<Coord> = HashSet::new();
-    distances[0][0] = 0;
-    queue.push(HeapElement::new(0, 0, 0));
-    while !queue.is_empty() {
-        // if visited.len() % 1000 == 0 {
-        //     println!("Visited {} nodes", visited.len());
-        // }
-        let current = queue.pop().unwrap();
-        let position = current.position;
-        let base_dist = current.distance;
-        if position == (max_x, max_y) {
-            return base_dist;
-        }
-        // If this is greater than the best known value, just skip it.
-        if base_dist > distances[position.0][position.1] {
-            continue;
-        }
-        let x = position.0;
-        let y = position.1;
-
-        if x > 0 {
-            let step = map[x - 1][y];
-            let current = distances[x - 1][y];
-            if base_dist + step < current {
-                distances[x - 1][y] = base_dist + step;
-                queue.push(HeapElement::new(x - 1, y, base_dist + step))
-            }
-        }
-        if y > 0 {
-            let step = map[x][y - 1];
-            let current = distances[x][y - 1];
-            if base_dist + step < current {
-                distances[x][y - 1] = base_dist + step;
-                queue.push(HeapElement::new(x, y - 1, base_dist + step))
-            }
-        }
-        if x < max_x {
-            let step = map[x + 1][y];
-            let current = distances[x + 1][y];
-            if base_dist + step < current {
-                distances[x + 1][y] = base_dist + step;
-                queue.push(HeapElement::new(x + 1, y, base_dist + step))
-            }
+fn calc_dist_graph(map: &Grid<i64>) -> Option<i64> {
+    let mut graph: Graph<Coord, i64> = Graph::weighted(true);
+    for (position, _) in map.iter() {
+        for next in map.neighbors4(position) {
+            graph.add_weighted_edge(position, next, map[next]);
         }
-        if y < max_y {
-            let step = map[x][y + 1];
-            let current = distances[x][y + 1];
-            if base_dist + step < current {
-                distances[x][y + 1] = base_dist + step;
-                queue.push(HeapElement::new(x, y + 1, base_dist + step))
-            }
-        }
-        visited.insert((x, y));
     }
-
-    panic!("Should never reach here");
+    graph.distance(&(0, 0), &(map.width() - 1, map.height() - 1))
 }
 
-fn multiply_map(map: &[Vec<i64>], multiple: usize) -> Vec<Vec<i64>> {
-    let x_len = map.len();
-    let y_len = map[0].len();
-    let mut result = vec![vec![0; multiple * y_len]; multiple * x_len];
-
-    for (x, col) in map.iter().enumerate() {
-        for (y, value) in col.iter().enumerate() {
-            for (x_mul, y_mul) in iproduct!(0..multiple, 0..multiple) {
-                let mut new_value = value + (x_mul as i64) + (y_mul as i64);
-                if new_value > 9 {
-                    new_value -= 9;
-                }
-                // println!("({}, {})={} -> ({}, {})={}", x, y, value, x + x_mul * x_len, y + y_mul * y_len, new_value);
-                result[x + x_mul * x_len][y + y_mul * y_len] = new_value;
+fn multiply_map(map: &Grid<i64>, multiple: usize) -> Grid<i64> {
+    let x_len = map.width();
+    let y_len = map.height();
+    let mut result = Grid::new(multiple * x_len, multiple * y_len, 0);
+
+    for ((x, y), value) in map.iter() {
+        for (x_mul, y_mul) in iproduct!(0..multiple, 0..multiple) {
+            let mut new_value = value + (x_mul as i64) + (y_mul as i64);
+            if new_value > 9 {
+                new_value -= 9;
             }
+            // println!("({}, {})={} -> ({}, {})={}", x, y, value, x + x_mul * x_len, y + y_mul * y_len, new_value);
+            result[(x + x_mul * x_len, y + y_mul * y_len)] = new_value;
         }
     }
     result
 }
 
 #[aoc(day15, part1)]
-fn part1(map: &[Vec<i64>]) -> Result<i64> {
+fn part1(map: &Grid<i64>) -> Result<i64> {
     Ok(calc_dist(map))
 }
 
 #[aoc(day15, part1, fast)]
-fn part1_fast(map: &[Vec<i64>]) -> Result<i64> {
+fn part1_fast(map: &Grid<i64>) -> Result<i64> {
     Ok(calc_dist2(map))
 }
 
+#[aoc(day15, part1, graph)]
+fn part1_graph(map: &Grid<i64>) -> Result<i64> {
+    calc_dist_graph(map).context("No path to the exit")
+}
+
 #[aoc(day15, part2, fast)]
-fn part2(input: &[Vec<i64>]) -> Result<i64> {
+fn part2(input: &Grid<i64>) -> Result<i64> {
     let map = multiply_map(input, 5);
-    let max_x = map.len() - 1;
-    let max_y = map[0].len() - 1;
-    println!("Multiplied to {}x{}", max_x, max_y);
+    println!("Multiplied to {}x{}", map.width(), map.height());
     Ok(calc_dist2(&map))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::differential::{check_agreement, solver, without_each, Rng};
 
     const SMOKE: &str = "1163751742
 1381373672
@@ -224,10 +132,61 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn smoke1_graph() -> Result<()> {
+        let input = input_generator(SMOKE)?;
+        assert_eq!(40, part1_graph(&input)?);
+        Ok(())
+    }
+
     #[test]
     fn smoke2() -> Result<()> {
         let input = input_generator(SMOKE)?;
         assert_eq!(315, part2(&input)?);
         Ok(())
     }
+
+    #[test]
+    fn variants_agree() {
+        check_agreement(
+            30,
+            |rng: &mut Rng| {
+                let width = rng.range(1, 8);
+                (0..rng.range(1, 8))
+                    .map(|_| (0..width).map(|_| rng.range(1, 9) as u8).collect())
+                    .collect::<Vec<Vec<u8>>>()
+            },
+            |rows| {
+                let mut result = vec![];
+                if rows.len() > 1 {
+                    result.extend(without_each(rows));
+                }
+                if rows[0].len() > 1 {
+                    for x in 0..rows[0].len() {
+                        let mut smaller = rows.clone();
+                        smaller.iter_mut().for_each(|row| {
+                            row.remove(x);
+                        });
+                        result.push(smaller);
+                    }
+                }
+                result
+            },
+            |rows| {
+                rows.iter()
+                    .map(|row| row.iter().map(|d| d.to_string()).collect::<String>())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            },
+            &[
+                solver("part1", &|s| Ok(part1(&input_generator(s)?)?.to_string())),
+                solver("part1_fast", &|s| {
+                    Ok(part1_fast(&input_generator(s)?)?.to_string())
+                }),
+                solver("part1_graph", &|s| {
+                    Ok(part1_graph(&input_generator(s)?)?.to_string())
+                }),
+            ],
+        );
+    }
 }
@@ -1,6 +1,18 @@
-use anyhow::{bail, Context, Result};
+use std::{fmt::Display, str::FromStr};
 
-#[derive(Debug, PartialEq, Eq)]
+use anyhow::{bail, ensure, Context, Result};
+use itertools::Itertools;
+use num_bigint::BigUint;
+
+use crate::Count;
+
+/// What packets are decoded and evaluated in: `u64` as the puzzle intends,
+/// where literals and results that do not fit are errors, or `BigUint`.
+trait Value: Count + Ord + From<u8> + Display {}
+
+impl<C: Count + Ord + From<u8> + Display> Value for C {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Operator {
     Sum,
     Product,
@@ -12,37 +24,78 @@ enum Operator {
 }
 
 impl Operator {
-    fn apply(&self, contents: &[Packet]) -> Result<u64> {
-        let values: Vec<u64> = contents
+    fn apply<C: Value>(&self, contents: &[Packet<C>]) -> Result<C> {
+        let values = contents
             .iter()
-            .map(|p| p.value())
-            .collect::<Result<Vec<u64>>>()?;
+            .map(Packet::value)
+            .collect::<Result<Vec<C>>>()?;
+        self.combine(&values)
+    }
+
+    /// The operator's result for the values of its sub-packets.
+    fn combine<C: Value>(&self, values: &[C]) -> Result<C> {
+        let compare = |test: fn(&C, &C) -> bool| -> Result<C> {
+            ensure!(
+                values.len() == 2,
+                "{:?} needs two operands, not {}",
+                self,
+                values.len()
+            );
+            Ok(if test(&values[0], &values[1]) {
+                C::one()
+            } else {
+                C::zero()
+            })
+        };
+        let overflow = || format!("{:?} overflows {}", self, std::any::type_name::<C>());
         match self {
-            Operator::Sum => Ok(values.into_iter().sum()),
-            Operator::Product => Ok(values.into_iter().product()),
-            Operator::Minimum => values.into_iter().min().context("Insufficient values"),
-            Operator::Maximum => values.into_iter().max().context("Insufficient values"),
-            Operator::GreaterThan => {
-                if values[0] > values[1] {
-                    Ok(1)
-                } else {
-                    Ok(0)
-                }
-            }
-            Operator::LessThan => {
-                if values[0] < values[1] {
-                    Ok(1)
-                } else {
-                    Ok(0)
-                }
-            }
-            Operator::EqualTo => {
-                if values[0] == values[1] {
-                    Ok(1)
-                } else {
-                    Ok(0)
-                }
-            }
+            Operator::Sum => values
+                .iter()
+                .try_fold(C::zero(), |acc, v| acc.checked_add(v))
+                .with_context(overflow),
+            Operator::Product => values
+                .iter()
+                .try_fold(C::one(), |acc, v| acc.checked_mul(v))
+                .with_context(overflow),
+            Operator::Minimum => values.iter().min().cloned().context("Insufficient values"),
+            Operator::Maximum => values.iter().max().cloned().context("Insufficient values"),
+            Operator::GreaterThan => compare(C::gt),
+            Operator::LessThan => compare(C::lt),
+            Operator::EqualTo => compare(C::eq),
+        }
+    }
+}
+
+impl Operator {
+    /// The name used in expressions and when displaying packets.
+    fn name(&self) -> &'static str {
+        match self {
+            Operator::Sum => "sum",
+            Operator::Product => "product",
+            Operator::Minimum => "min",
+            Operator::Maximum => "max",
+            Operator::GreaterThan => "gt",
+            Operator::LessThan => "lt",
+            Operator::EqualTo => "eq",
+        }
+    }
+
+    fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Operator::GreaterThan | Operator::LessThan | Operator::EqualTo
+        )
+    }
+
+    fn id(&self) -> u8 {
+        match self {
+            Operator::Sum => 0,
+            Operator::Product => 1,
+            Operator::Minimum => 2,
+            Operator::Maximum => 3,
+            Operator::GreaterThan => 5,
+            Operator::LessThan => 6,
+            Operator::EqualTo => 7,
         }
     }
 }
@@ -59,118 +112,719 @@ impl TryFrom<u8> for Operator {
             5 => Operator::GreaterThan,
             6 => Operator::LessThan,
             7 => Operator::EqualTo,
-            _ => bail!("Invalid value"),
+            4 => bail!("Type id 4 is a literal, not an operator"),
+            _ => bail!("Invalid operator type id {}", value),
         })
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum Packet {
-    Literal(u8, u64),
-    Operator(u8, Operator, Vec<Packet>),
+/// How an operator packet says where its sub-packets end.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LengthType {
+    /// Type 0: the total number of bits in the sub-packets.
+    #[default]
+    Bits,
+    /// Type 1: the number of immediate sub-packets.
+    Count,
 }
 
-impl Packet {
-    fn from_iter(iter: &mut impl Iterator<Item = u8>) -> Result<Packet> {
-        let version = bits_to_int(iter, 3)? as u8;
-        let id = bits_to_int(iter, 3)? as u8;
-
-        // println!("{} {}", version, id);
-        if id == 4 {
-            let mut value = 0u64;
-            let mut last = false;
-            while !last {
-                last = read_bit(iter)? == 0;
-                value <<= 4;
-                value += bits_to_int(iter, 4)? as u64;
+impl FromStr for LengthType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bits" => Ok(LengthType::Bits),
+            "count" => Ok(LengthType::Count),
+            _ => bail!("Length type must be bits or count, not {}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Packet<C = u64> {
+    Literal(u8, C),
+    Operator(u8, Operator, Vec<Packet<C>>),
+}
+
+/// Why a transmission could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DecodeReason {
+    InvalidHex(char),
+    /// The named field runs past the end of the transmission, or past the
+    /// bit length of the enclosing operator's sub-packets.
+    Truncated(&'static str),
+    LiteralTooLarge,
+    Operands(Operator, usize),
+    TrailingBits,
+    /// Sub-packets nested more than [`MAX_DEPTH`] deep.
+    TooDeep,
+}
+
+impl Display for DecodeReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeReason::InvalidHex(c) => write!(f, "Invalid hex digit {:?}", c),
+            DecodeReason::Truncated(field) => write!(f, "Truncated {}", field),
+            DecodeReason::LiteralTooLarge => write!(f, "Literal too large"),
+            DecodeReason::Operands(operator, count) => {
+                write!(f, "{:?} needs two operands, not {}", operator, count)
+            }
+            DecodeReason::TrailingBits => write!(f, "Non-zero padding after the packet"),
+            DecodeReason::TooDeep => write!(f, "Packets nested over {} deep", MAX_DEPTH),
+        }
+    }
+}
+
+/// A decoding failure at bit `offset` of the transmission. `path` holds the
+/// sub-packet indexes leading from the outermost packet to the broken one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DecodeError {
+    offset: usize,
+    path: Vec<usize>,
+    reason: DecodeReason,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at bit {} in ", self.reason, self.offset)?;
+        if self.path.is_empty() {
+            write!(f, "the outermost packet")
+        } else {
+            write!(f, "sub-packet {}", self.path.iter().join("."))
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// How deep sub-packets may nest. Decoding recurses once per level, so this
+/// keeps a hostile transmission from overflowing the stack.
+const MAX_DEPTH: usize = 256;
+
+struct Decoder {
+    bits: Vec<u8>,
+    pos: usize,
+    /// Reads may not go past here, the end of the current length type 0
+    /// sub-packets or of the whole transmission.
+    limit: usize,
+    /// Its length is the nesting depth of the packet being read.
+    path: Vec<usize>,
+    /// Where each packet starts, in the order of [`Packet::iter`].
+    offsets: Vec<usize>,
+}
+
+impl Decoder {
+    fn new(hex: &str) -> Result<Self, DecodeError> {
+        let mut bits = vec![];
+        for (idx, c) in hex.chars().enumerate() {
+            let nibble = c.to_digit(16).ok_or(DecodeError {
+                offset: idx * 4,
+                path: vec![],
+                reason: DecodeReason::InvalidHex(c),
+            })?;
+            bits.extend((0..4).rev().map(|shift| (nibble >> shift) as u8 & 1));
+        }
+        Ok(Self {
+            limit: bits.len(),
+            bits,
+            pos: 0,
+            path: vec![],
+            offsets: vec![],
+        })
+    }
+
+    fn error(&self, offset: usize, reason: DecodeReason) -> DecodeError {
+        DecodeError {
+            offset,
+            path: self.path.clone(),
+            reason,
+        }
+    }
+
+    fn read(&mut self, len: usize, field: &'static str) -> Result<u64, DecodeError> {
+        if self.pos + len > self.limit {
+            return Err(self.error(self.pos, DecodeReason::Truncated(field)));
+        }
+        let result = self.bits[self.pos..self.pos + len]
+            .iter()
+            .fold(0, |acc, bit| acc << 1 | *bit as u64);
+        self.pos += len;
+        Ok(result)
+    }
+
+    fn packet<C: Value>(&mut self) -> Result<Packet<C>, DecodeError> {
+        let start = self.pos;
+        self.offsets.push(start);
+        let version = self.read(3, "version")? as u8;
+        let id = self.read(3, "type id")? as u8;
+
+        let operator = match Operator::try_from(id) {
+            Ok(operator) => operator,
+            // Of the three bit type ids only 4 is not an operator
+            Err(_) => return self.literal(version),
+        };
+        let mut contents = vec![];
+        if self.read(1, "length type")? == 0 {
+            let length = self.read(15, "sub-packet length")? as usize;
+            if self.pos + length > self.limit {
+                return Err(self.error(self.pos, DecodeReason::Truncated("sub-packets")));
+            }
+            let outer = std::mem::replace(&mut self.limit, self.pos + length);
+            while self.pos < self.limit {
+                contents.push(self.nested(contents.len())?);
             }
-            Ok(Packet::Literal(version, value))
+            self.limit = outer;
         } else {
-            let length_type = read_bit(iter)?;
-            let mut contents = vec![];
-            if length_type == 0 {
-                let bit_length = bits_to_int(iter, 15)? as usize;
-                #[allow(clippy::needless_collect)] // Prevents infinite recursion of types
-                let nested_bits: Vec<u8> = iter.take(bit_length).collect();
-                let mut nested_iter = nested_bits.into_iter();
-                // iter.skip(bit_length);
-                while let Ok(nested) = Packet::from_iter(&mut nested_iter) {
-                    contents.push(nested);
-                }
-            } else if length_type == 1 {
-                let packet_count = bits_to_int(iter, 11)? as usize;
-                while contents.len() < packet_count {
-                    contents.push(Packet::from_iter(iter)?);
-                }
-            } else {
-                panic!("Impossible");
+            let count = self.read(11, "sub-packet count")? as usize;
+            while contents.len() < count {
+                contents.push(self.nested(contents.len())?);
+            }
+        }
+        if operator.is_comparison() && contents.len() != 2 {
+            return Err(self.error(start, DecodeReason::Operands(operator, contents.len())));
+        }
+        Ok(Packet::Operator(version, operator, contents))
+    }
+
+    fn literal<C: Value>(&mut self, version: u8) -> Result<Packet<C>, DecodeError> {
+        let mut value = C::zero();
+        loop {
+            let group = self.pos;
+            let more = self.read(1, "literal")? == 1;
+            let nibble = C::from(self.read(4, "literal")? as u8);
+            value = value
+                .checked_mul(&C::from(16))
+                .and_then(|v| v.checked_add(&nibble))
+                .ok_or_else(|| self.error(group, DecodeReason::LiteralTooLarge))?;
+            if !more {
+                return Ok(Packet::Literal(version, value));
             }
-            Ok(Packet::Operator(version, id.try_into()?, contents))
         }
     }
 
-    fn value(&self) -> Result<u64> {
+    fn nested<C: Value>(&mut self, index: usize) -> Result<Packet<C>, DecodeError> {
+        if self.path.len() == MAX_DEPTH {
+            return Err(self.error(self.pos, DecodeReason::TooDeep));
+        }
+        self.path.push(index);
+        let result = self.packet()?;
+        self.path.pop();
+        Ok(result)
+    }
+}
+
+/// Decodes a hex transmission holding one packet plus zero padding.
+fn decode(hex: &str) -> Result<Packet, DecodeError> {
+    Ok(decode_transmission(hex)?.packet)
+}
+
+/// Like [`decode`], but in any [`Value`] and keeping the bit offset of every
+/// packet.
+fn decode_transmission<C: Value>(hex: &str) -> Result<Transmission<C>, DecodeError> {
+    let mut decoder = Decoder::new(hex)?;
+    let packet = decoder.packet()?;
+    if let Some(idx) = decoder.bits[decoder.pos..].iter().position(|b| *b != 0) {
+        return Err(decoder.error(decoder.pos + idx, DecodeReason::TrailingBits));
+    }
+    Ok(Transmission {
+        packet,
+        offsets: decoder.offsets,
+    })
+}
+
+impl<C: Value> Packet<C> {
+    fn version(&self) -> u8 {
         match self {
-            Packet::Literal(_, value) => Ok(*value),
+            Packet::Literal(version, _) | Packet::Operator(version, _, _) => *version,
+        }
+    }
+
+    /// The immediate sub-packets.
+    fn sub_packets(&self) -> &[Packet<C>] {
+        match self {
+            Packet::Literal(..) => &[],
+            Packet::Operator(_, _, contents) => contents,
+        }
+    }
+
+    /// This packet followed by all packets nested in it, depth first.
+    fn iter(&self) -> Packets<'_, C> {
+        Packets { stack: vec![self] }
+    }
+
+    fn value(&self) -> Result<C> {
+        match self {
+            Packet::Literal(_, value) => Ok(value.clone()),
             Packet::Operator(_, operator, contents) => operator.apply(contents),
         }
     }
 }
 
-fn read_bit(iter: &mut impl Iterator<Item = u8>) -> Result<u8> {
-    iter.next().context("Insufficient bits")
+/// A packet alongside its value and those of its sub-packets, each worked
+/// out once from the values below it. The value is `None` where evaluation
+/// fails.
+struct Evaluated<'a, C> {
+    packet: &'a Packet<C>,
+    value: Option<C>,
+    sub_packets: Vec<Evaluated<'a, C>>,
 }
 
-fn bits_to_int(iter: &mut impl Iterator<Item = u8>, len: usize) -> Result<u64> {
-    let mut result = 0u64;
-    for _ in 0..len {
-        result <<= 1;
-        result += read_bit(iter)? as u64;
+impl<'a, C: Value> Evaluated<'a, C> {
+    /// Recurses once per level, relying on [`Decoder`] rejecting packets
+    /// nested more than [`MAX_DEPTH`] deep.
+    fn new(packet: &'a Packet<C>) -> Self {
+        let sub_packets: Vec<_> = packet.sub_packets().iter().map(Evaluated::new).collect();
+        let value = match packet {
+            Packet::Literal(_, value) => Some(value.clone()),
+            Packet::Operator(_, operator, _) => sub_packets
+                .iter()
+                .map(|p| p.value.clone())
+                .collect::<Option<Vec<C>>>()
+                .and_then(|values| operator.combine(&values).ok()),
+        };
+        Self {
+            packet,
+            value,
+            sub_packets,
+        }
+    }
+
+    /// Writes the packet as an S-expression such as
+    /// `(sum v3 =12 (lit v1 5) (max v2 =7 (lit v0 7)))`, or with `{:#}` as
+    /// one packet per line indented by depth. Operators show their computed
+    /// value, or `=?` if it cannot be computed. If `offsets` are given, each
+    /// packet also shows where it starts as `@bit`.
+    fn write_tree(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        offsets: &mut Option<std::slice::Iter<usize>>,
+        depth: usize,
+    ) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "{:1$}", "", depth * 2)?;
+        } else {
+            write!(f, "(")?;
+        }
+        match self.packet {
+            Packet::Literal(..) => write!(f, "lit")?,
+            Packet::Operator(_, operator, _) => write!(f, "{}", operator.name())?,
+        }
+        write!(f, " v{}", self.packet.version())?;
+        if let Some(offset) = offsets.as_mut().and_then(|o| o.next()) {
+            write!(f, " @{}", offset)?;
+        }
+        match (self.packet, &self.value) {
+            (Packet::Literal(..), Some(value)) => write!(f, " {}", value)?,
+            (_, Some(value)) => write!(f, " ={}", value)?,
+            (_, None) => write!(f, " =?")?,
+        }
+        for packet in &self.sub_packets {
+            if f.alternate() {
+                writeln!(f)?;
+            } else {
+                write!(f, " ")?;
+            }
+            packet.write_tree(f, offsets, depth + 1)?;
+        }
+        if !f.alternate() {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: Value> Display for Packet<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Evaluated::new(self).write_tree(f, &mut None, 0)
+    }
+}
+
+/// Pre-order iterator over a packet and its nested packets.
+struct Packets<'a, C> {
+    stack: Vec<&'a Packet<C>>,
+}
+
+impl<'a, C: Value> Iterator for Packets<'a, C> {
+    type Item = &'a Packet<C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let packet = self.stack.pop()?;
+        self.stack.extend(packet.sub_packets().iter().rev());
+        Some(packet)
+    }
+}
+
+/// A decoded packet along with the bit offset of each packet in
+/// [`Packet::iter`] order. Displays like the packet, with offsets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Transmission<C = u64> {
+    packet: Packet<C>,
+    offsets: Vec<usize>,
+}
+
+impl<C: Value> Display for Transmission<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Evaluated::new(&self.packet).write_tree(f, &mut Some(self.offsets.iter()), 0)
+    }
+}
+
+impl Packet<BigUint> {
+    /// The packets where a `u64` evaluation would first overflow: their
+    /// value does not fit in a `u64`, but that of every sub-packet does.
+    fn u64_overflows(&self) -> Vec<&Packet<BigUint>> {
+        let mut result = vec![];
+        Evaluated::new(self).u64_overflows(&mut result);
+        result
+    }
+}
+
+impl<'a> Evaluated<'a, BigUint> {
+    fn u64_overflows(&self, result: &mut Vec<&'a Packet<BigUint>>) {
+        let fits = |evaluated: &Evaluated<BigUint>| {
+            evaluated
+                .value
+                .as_ref()
+                .is_some_and(|value| u64::try_from(value).is_ok())
+        };
+        if !fits(self) && self.sub_packets.iter().all(fits) {
+            result.push(self.packet);
+        }
+        for packet in &self.sub_packets {
+            packet.u64_overflows(result);
+        }
+    }
+}
+
+impl Packet {
+    /// Serializes the packet, asking `length_type` which form each operator
+    /// packet should use.
+    fn encode(&self, length_type: &mut impl FnMut(&Packet) -> LengthType) -> Result<Vec<u8>> {
+        let mut bits = vec![];
+        self.write_bits(&mut bits, length_type)?;
+        Ok(bits)
+    }
+
+    fn write_bits(
+        &self,
+        bits: &mut Vec<u8>,
+        length_type: &mut impl FnMut(&Packet) -> LengthType,
+    ) -> Result<()> {
+        match self {
+            Packet::Literal(version, value) => {
+                int_to_bits(bits, *version as u64, 3)?;
+                int_to_bits(bits, 4, 3)?;
+                let groups = (64 - value.leading_zeros() as usize).max(1).div_ceil(4);
+                for group in (0..groups).rev() {
+                    bits.push(if group == 0 { 0 } else { 1 });
+                    int_to_bits(bits, (value >> (group * 4)) & 0xf, 4)?;
+                }
+            }
+            Packet::Operator(version, operator, contents) => {
+                int_to_bits(bits, *version as u64, 3)?;
+                int_to_bits(bits, operator.id() as u64, 3)?;
+                match length_type(self) {
+                    LengthType::Bits => {
+                        let mut nested = vec![];
+                        for packet in contents {
+                            packet.write_bits(&mut nested, length_type)?;
+                        }
+                        bits.push(0);
+                        int_to_bits(bits, nested.len() as u64, 15)
+                            .context("Sub-packets too long for length type 0")?;
+                        bits.append(&mut nested);
+                    }
+                    LengthType::Count => {
+                        bits.push(1);
+                        int_to_bits(bits, contents.len() as u64, 11)
+                            .context("Too many sub-packets for length type 1")?;
+                        for packet in contents {
+                            packet.write_bits(bits, length_type)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The packet as a hex transmission, zero padded to a whole digit.
+    fn to_hex(&self, length_type: &mut impl FnMut(&Packet) -> LengthType) -> Result<String> {
+        Ok(bits_to_hex(&self.encode(length_type)?))
+    }
+}
+
+/// An arithmetic expression that compiles to a BITS transmission, such as
+/// `max(3, 4*5) < sum(1,2,3)`. `+` and `*` build sum and product packets and
+/// `<`, `>` and `==` the comparisons, alongside the calls `sum`, `product`,
+/// `min`, `max`, `gt`, `lt` and `eq`. Every packet gets version 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Literal(u64),
+    Apply(Operator, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(u64),
+    Name(String),
+    Symbol(&'static str),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>> {
+    let mut result = vec![];
+    let mut chars = source.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        let token = if c.is_whitespace() {
+            continue;
+        } else if c.is_ascii_digit() {
+            let mut number = c.to_string();
+            while let Some((_, d)) = chars.next_if(|(_, d)| d.is_ascii_digit()) {
+                number.push(d);
+            }
+            Token::Number(
+                number
+                    .parse()
+                    .with_context(|| format!("Number too large at {}", pos))?,
+            )
+        } else if c.is_ascii_alphabetic() {
+            let mut name = c.to_string();
+            while let Some((_, d)) = chars.next_if(|(_, d)| d.is_ascii_alphanumeric()) {
+                name.push(d);
+            }
+            Token::Name(name)
+        } else if c == '=' && chars.next_if(|(_, d)| *d == '=').is_some() {
+            Token::Symbol("==")
+        } else {
+            match "()+*<>,".find(c) {
+                Some(idx) => Token::Symbol(&"()+*<>,"[idx..idx + 1]),
+                None => bail!("Unexpected {:?} at {}", c, pos),
+            }
+        };
+        result.push((pos, token));
     }
     Ok(result)
 }
 
-fn parse_nibble(nibble: char) -> u8 {
-    // println!("N={}", nibble);
-    if ('0'..='9').contains(&nibble) {
-        nibble as u8 - b'0'
-    } else if ('A'..='F').contains(&nibble) {
-        nibble as u8 + 10 - b'A'
-    } else {
-        panic!("Invalid nibble")
+/// Recursive descent over the tokens, loosest binding first.
+struct ExprParser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    len: usize,
+    /// How many brackets and calls enclose the current token.
+    depth: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.1)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.len, |t| t.0)
+    }
+
+    fn eat(&mut self, symbol: &'static str) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<()> {
+        ensure!(self.eat(symbol), "Expected {} at {}", symbol, self.offset());
+        Ok(())
+    }
+
+    /// Runs `parse` one bracket or call deeper, failing rather than
+    /// recursing without bound.
+    fn nested<T>(
+        &mut self,
+        offset: usize,
+        parse: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        ensure!(
+            self.depth < MAX_DEPTH,
+            "Nested over {} deep at {}",
+            MAX_DEPTH,
+            offset
+        );
+        self.depth += 1;
+        let result = parse(self)?;
+        self.depth -= 1;
+        Ok(result)
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let left = self.sum()?;
+        for (symbol, operator) in [
+            ("<", Operator::LessThan),
+            (">", Operator::GreaterThan),
+            ("==", Operator::EqualTo),
+        ] {
+            if self.eat(symbol) {
+                let right = self.sum()?;
+                return Ok(Expr::Apply(operator, vec![left, right]));
+            }
+        }
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        let mut terms = vec![self.product()?];
+        while self.eat("+") {
+            terms.push(self.product()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            Expr::Apply(Operator::Sum, terms)
+        })
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        let mut factors = vec![self.atom()?];
+        while self.eat("*") {
+            factors.push(self.atom()?);
+        }
+        Ok(if factors.len() == 1 {
+            factors.pop().unwrap()
+        } else {
+            Expr::Apply(Operator::Product, factors)
+        })
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        let offset = self.offset();
+        let token = self
+            .peek()
+            .cloned()
+            .with_context(|| format!("Unexpected end at {}", offset))?;
+        self.pos += 1;
+        match token {
+            Token::Number(value) => Ok(Expr::Literal(value)),
+            Token::Symbol("(") => self.nested(offset, |parser| {
+                let inner = parser.comparison()?;
+                parser.expect(")")?;
+                Ok(inner)
+            }),
+            Token::Name(name) => {
+                let operator = (0..8)
+                    .filter_map(|id| Operator::try_from(id).ok())
+                    .find(|operator| operator.name() == name)
+                    .with_context(|| format!("Unknown function {} at {}", name, offset))?;
+                self.expect("(")?;
+                self.nested(offset, |parser| {
+                    let mut args = vec![];
+                    if !parser.eat(")") {
+                        loop {
+                            args.push(parser.comparison()?);
+                            if parser.eat(")") {
+                                break;
+                            }
+                            parser.expect(",")?;
+                        }
+                    }
+                    Ok(Expr::Apply(operator, args))
+                })
+            }
+            Token::Symbol(symbol) => bail!("Unexpected {} at {}", symbol, offset),
+        }
     }
 }
 
-#[aoc_generator(day16)]
-fn input_generator(input: &str) -> Result<Packet> {
-    let mut iter = bit_iterator(input.chars().map(parse_nibble));
+impl FromStr for Expr {
+    type Err = anyhow::Error;
 
-    Packet::from_iter(&mut iter)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = ExprParser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            len: s.len(),
+            depth: 0,
+        };
+        let result = parser.comparison()?;
+        ensure!(
+            parser.peek().is_none(),
+            "Unexpected trailing input at {}",
+            parser.offset()
+        );
+        // Operators add packets without brackets, as in `1 < 2 + 3 * (...)`
+        ensure!(
+            result.depth() <= MAX_DEPTH,
+            "Packets nested over {} deep",
+            MAX_DEPTH
+        );
+        Ok(result)
+    }
 }
 
-fn byte_to_bits(byte: u8) -> [u8; 4] {
-    let mut result = [0; 4];
-    let mut byte = byte;
-    for idx in (0..4).rev() {
-        result[idx] = byte & 1;
-        byte >>= 1;
+impl Expr {
+    /// How deep the innermost packet nests, as counted by [`Decoder`].
+    fn depth(&self) -> usize {
+        match self {
+            Expr::Literal(_) => 0,
+            Expr::Apply(_, args) => args.iter().map(|arg| arg.depth() + 1).max().unwrap_or(0),
+        }
+    }
+
+    fn to_packet(&self) -> Packet {
+        match self {
+            Expr::Literal(value) => Packet::Literal(0, *value),
+            Expr::Apply(operator, args) => {
+                Packet::Operator(0, *operator, args.iter().map(Expr::to_packet).collect())
+            }
+        }
+    }
+
+    /// The hex transmission for the expression, with every operator using
+    /// `length_type`.
+    fn compile(&self, length_type: LengthType) -> Result<String> {
+        self.to_packet().to_hex(&mut |_| length_type)
     }
-    result
 }
 
-fn bit_iterator(bytes: impl Iterator<Item = u8>) -> impl Iterator<Item = u8> {
-    bytes.flat_map(byte_to_bits)
+/// Compiles an expression such as `max(3, 4*5) < sum(1,2,3)` into a hex
+/// transmission, for crafting decoder inputs by hand.
+pub fn compile(source: &str, length_type: LengthType) -> Result<String> {
+    source.parse::<Expr>()?.compile(length_type)
+}
+
+fn int_to_bits(bits: &mut Vec<u8>, value: u64, len: usize) -> Result<()> {
+    ensure!(
+        len >= 64 || value >> len == 0,
+        "{} does not fit in {} bits",
+        value,
+        len
+    );
+    for idx in (0..len).rev() {
+        bits.push((value >> idx) as u8 & 1);
+    }
+    Ok(())
+}
+
+fn bits_to_hex(bits: &[u8]) -> String {
+    bits.chunks(4)
+        .map(|chunk| {
+            let nibble = (0..4).fold(0, |acc, idx| {
+                (acc << 1) | chunk.get(idx).copied().unwrap_or(0)
+            });
+            char::from_digit(nibble as u32, 16)
+                .unwrap()
+                .to_ascii_uppercase()
+        })
+        .collect()
+}
+
+#[aoc_generator(day16)]
+fn input_generator(input: &str) -> Result<Packet> {
+    Ok(decode(input.trim())?)
 }
 
 #[aoc(day16, part1)]
 fn part1(packet: &Packet) -> Result<i64> {
-    match packet {
-        Packet::Literal(version, _) => Ok(*version as i64),
-        Packet::Operator(version, _, contents) => {
-            let inner_sum: i64 = contents.iter().map(|p| part1(p)).flatten().sum();
-            Ok(inner_sum + *version as i64)
-        }
-    }
+    Ok(packet.iter().map(|p| p.version() as i64).sum())
 }
 
 #[aoc(day16, part2)]
@@ -178,9 +832,52 @@ fn part2(input: &Packet) -> Result<u64> {
     input.value()
 }
 
+#[aoc_generator(day16, part2, big)]
+fn input_generator_big(input: &str) -> Result<Packet<BigUint>> {
+    Ok(decode_transmission(input.trim())?.packet)
+}
+
+#[aoc(day16, part2, big)]
+fn part2_big(input: &Packet<BigUint>) -> Result<BigUint> {
+    for packet in input.u64_overflows() {
+        println!("u64 evaluation overflows at {}", packet);
+    }
+    input.value()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::differential::Rng;
+
+    /// Reference evaluation straight from the expression tree, independent
+    /// of [`Packet::value`]. Fails on overflow and on bad operand counts.
+    fn eval(expr: &Expr) -> Result<u64> {
+        let (operator, args) = match expr {
+            Expr::Literal(value) => return Ok(*value),
+            Expr::Apply(operator, args) => (operator, args),
+        };
+        let values = args.iter().map(eval).collect::<Result<Vec<u64>>>()?;
+        let compare = |test: fn(&u64, &u64) -> bool| -> Result<u64> {
+            ensure!(values.len() == 2, "{:?} needs two operands", operator);
+            Ok(test(&values[0], &values[1]) as u64)
+        };
+        match operator {
+            Operator::Sum => values
+                .iter()
+                .try_fold(0u64, |acc, v| acc.checked_add(*v))
+                .context("Overflow"),
+            Operator::Product => values
+                .iter()
+                .try_fold(1u64, |acc, v| acc.checked_mul(*v))
+                .context("Overflow"),
+            Operator::Minimum => values.iter().copied().min().context("No operands"),
+            Operator::Maximum => values.iter().copied().max().context("No operands"),
+            Operator::GreaterThan => compare(u64::gt),
+            Operator::LessThan => compare(u64::lt),
+            Operator::EqualTo => compare(u64::eq),
+        }
+    }
 
     #[test]
     fn values() -> Result<()> {
@@ -209,6 +906,408 @@ mod tests {
         Ok(())
     }
 
+    fn random_packet(rng: &mut Rng, depth: usize) -> Packet {
+        let version = rng.range(0, 7) as u8;
+        if depth == 0 || rng.chance(40) {
+            let value = match rng.range(0, 2) {
+                0 => rng.range(0, 15) as u64,
+                1 => rng.range(0, 1 << 20) as u64,
+                _ => rng.next_u64(),
+            };
+            Packet::Literal(version, value)
+        } else {
+            let operator = rng.range(0, 6) as u8;
+            let operator = Operator::try_from(if operator >= 4 {
+                operator + 1
+            } else {
+                operator
+            })
+            .unwrap();
+            let count = if operator.is_comparison() {
+                2
+            } else {
+                rng.range(0, 3)
+            };
+            let contents = (0..count).map(|_| random_packet(rng, depth - 1)).collect();
+            Packet::Operator(version, operator, contents)
+        }
+    }
+
+    #[test]
+    fn encode() -> Result<()> {
+        let always = |length_type| move |_: &Packet| length_type;
+        for hex in [
+            "D2FE28",
+            "38006F45291200",
+            "EE00D40C823060",
+            "8A004A801A8002F478",
+        ] {
+            let packet = input_generator(hex)?;
+            for length_type in [LengthType::Bits, LengthType::Count] {
+                let encoded = packet.to_hex(&mut always(length_type))?;
+                assert_eq!(packet, input_generator(&encoded)?);
+            }
+        }
+        assert_eq!(
+            "D2FE28",
+            input_generator("D2FE28")?.to_hex(&mut always(LengthType::Bits))?
+        );
+        assert_eq!(
+            "EE00D40C82306",
+            input_generator("EE00D40C823060")?.to_hex(&mut always(LengthType::Count))?
+        );
+        assert_eq!(
+            "38006F4529120",
+            input_generator("38006F45291200")?.to_hex(&mut always(LengthType::Bits))?
+        );
+
+        let too_many = Packet::Operator(0, Operator::Sum, vec![Packet::Literal(0, 0); 2048]);
+        assert!(too_many.encode(&mut always(LengthType::Count)).is_err());
+        assert!(too_many.encode(&mut always(LengthType::Bits)).is_ok());
+        assert!(Packet::Literal(8, 0)
+            .encode(&mut always(LengthType::Bits))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let mut rng = Rng::new(16);
+        for _ in 0..200 {
+            let packet = random_packet(&mut rng, 4);
+            let mut choices = Rng::new(rng.next_u64());
+            let hex = packet.to_hex(&mut |_| {
+                if choices.chance(50) {
+                    LengthType::Bits
+                } else {
+                    LengthType::Count
+                }
+            })?;
+            assert_eq!(packet, input_generator(&hex)?, "{}", hex);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn big_values() -> Result<()> {
+        let sum = "sum(18446744073709551615, 1)"
+            .parse::<Expr>()?
+            .compile(LengthType::Bits)?;
+        let error = part2(&input_generator(&sum)?).unwrap_err();
+        assert_eq!("Sum overflows u64", error.to_string());
+        let packet = input_generator_big(&sum)?;
+        assert_eq!(BigUint::from(u64::MAX) + 1u8, part2_big(&packet)?);
+        assert_eq!(vec![&packet], packet.u64_overflows());
+
+        let product = "max(product(4294967296, 4294967296), 7) * 2"
+            .parse::<Expr>()?
+            .compile(LengthType::Bits)?;
+        assert!(part2(&input_generator(&product)?).is_err());
+        let packet = input_generator_big(&product)?;
+        assert_eq!(BigUint::from(1u8) << 65, part2_big(&packet)?);
+        assert_eq!(
+            vec!["(product v0 =18446744073709551616 (lit v0 4294967296) (lit v0 4294967296))"],
+            packet
+                .u64_overflows()
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+        );
+
+        // A literal of 20 groups, 80 bits, each group holding 0xA
+        let mut long = vec![0, 0, 0, 1, 0, 0];
+        for group in (0..20).rev() {
+            long.push((group > 0) as u8);
+            int_to_bits(&mut long, 0xa, 4)?;
+        }
+        let long = bits_to_hex(&long);
+        let too_large = decode(&long).unwrap_err();
+        assert_eq!(DecodeReason::LiteralTooLarge, too_large.reason);
+        let packet = input_generator_big(&long)?;
+        assert_eq!(
+            BigUint::parse_bytes(&[b'A'; 20], 16),
+            Some(part2_big(&packet)?)
+        );
+        assert_eq!(1, packet.u64_overflows().len());
+
+        for hex in ["C200B40A82", "9C0141080250320F1802104A08"] {
+            assert_eq!(
+                BigUint::from(part2(&input_generator(hex)?)?),
+                part2_big(&input_generator_big(hex)?)?
+            );
+            assert!(input_generator_big(hex)?.u64_overflows().is_empty());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn display() -> Result<()> {
+        let transmission: Transmission = decode_transmission("C200B40A82")?;
+        assert_eq!(
+            "(sum v6 =3 (lit v6 1) (lit v2 2))",
+            transmission.packet.to_string()
+        );
+        assert_eq!(
+            "(sum v6 @0 =3 (lit v6 @18 1) (lit v2 @29 2))",
+            transmission.to_string()
+        );
+        assert_eq!(
+            "sum v6 @0 =3\n  lit v6 @18 1\n  lit v2 @29 2",
+            format!("{:#}", transmission)
+        );
+
+        let packet: Expr = "max(3, 4*5) < sum(1, 2)".parse()?;
+        let packet = decode(&packet.compile(LengthType::Bits)?)?;
+        assert_eq!(
+            "(lt v0 =0 (max v0 =20 (lit v0 3) (product v0 =20 (lit v0 4) (lit v0 5))) \
+             (sum v0 =3 (lit v0 1) (lit v0 2)))",
+            packet.to_string()
+        );
+        let broken: Packet = Packet::Operator(3, Operator::Minimum, vec![]);
+        assert_eq!("(min v3 =?)", broken.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn sub_packets() -> Result<()> {
+        let transmission: Transmission = decode_transmission("A0016C880162017C3686B18A3D4780")?;
+        let packets: Vec<&Packet> = transmission.packet.iter().collect();
+        assert_eq!(transmission.offsets.len(), packets.len());
+        assert_eq!(1, transmission.packet.sub_packets().len());
+        assert_eq!(
+            vec!["sum", "sum", "sum", "lit", "lit", "lit", "lit", "lit"],
+            packets
+                .iter()
+                .map(|p| match p {
+                    Packet::Literal(..) => "lit",
+                    Packet::Operator(_, operator, _) => operator.name(),
+                })
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(31, packets.iter().map(|p| p.version() as i64).sum::<i64>());
+        assert!(transmission.offsets.windows(2).all(|w| w[0] < w[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn decode_errors() -> Result<()> {
+        let error = |hex: &str| decode(hex).unwrap_err();
+        let mut count = |_: &Packet| LengthType::Count;
+
+        let bad_hex = error("D2FE2G");
+        assert_eq!(
+            (20, DecodeReason::InvalidHex('G')),
+            (bad_hex.offset, bad_hex.reason)
+        );
+        let truncated = error("D2FE");
+        assert_eq!(
+            (16, DecodeReason::Truncated("literal")),
+            (truncated.offset, truncated.reason)
+        );
+        let padding = error("D2FE29");
+        assert_eq!(
+            (23, DecodeReason::TrailingBits),
+            (padding.offset, padding.reason)
+        );
+        assert_eq!(Packet::Literal(6, 2021), decode("D2FE2800")?);
+
+        let mut too_large = vec![0, 0, 0, 1, 0, 0];
+        for group in (0..17).rev() {
+            too_large.push((group > 0) as u8);
+            int_to_bits(&mut too_large, 0xf, 4)?;
+        }
+        let too_large = error(&bits_to_hex(&too_large));
+        assert_eq!(
+            (86, DecodeReason::LiteralTooLarge),
+            (too_large.offset, too_large.reason)
+        );
+
+        let three = Packet::Operator(0, Operator::LessThan, vec![Packet::Literal(0, 1); 3]);
+        let operands = error(&three.to_hex(&mut count)?);
+        assert_eq!(
+            (0, DecodeReason::Operands(Operator::LessThan, 3)),
+            (operands.offset, operands.reason)
+        );
+        let nested = Packet::Operator(
+            0,
+            Operator::Sum,
+            vec![
+                Packet::Literal(0, 1),
+                Packet::Operator(0, Operator::EqualTo, vec![Packet::Literal(0, 1)]),
+            ],
+        );
+        let operands = error(&nested.to_hex(&mut count)?);
+        assert_eq!(vec![1], operands.path);
+        assert_eq!(29, operands.offset);
+        assert_eq!(
+            "EqualTo needs two operands, not 1 at bit 29 in sub-packet 1",
+            operands.to_string()
+        );
+        if let Packet::Operator(_, _, contents) = &nested {
+            assert!(contents[1].value().is_err());
+        }
+
+        // Bits left over inside a length type 0 operator used to be dropped
+        let mut junk = vec![0, 0, 0, 0, 0, 0, 0];
+        int_to_bits(&mut junk, 14, 15)?;
+        junk.extend(Packet::Literal(0, 1).encode(&mut count)?);
+        junk.extend([1, 1, 1]);
+        let junk = error(&bits_to_hex(&junk));
+        assert_eq!(
+            (36, vec![1], DecodeReason::Truncated("type id")),
+            (junk.offset, junk.path, junk.reason)
+        );
+
+        let nest = |depth| {
+            (0..depth).fold(Packet::Literal(0, 1), |packet, _| {
+                Packet::Operator(0, Operator::Sum, vec![packet])
+            })
+        };
+        assert_eq!(1, decode(&nest(MAX_DEPTH).to_hex(&mut count)?)?.value()?);
+        let too_deep = error(&nest(MAX_DEPTH + 1).to_hex(&mut count)?);
+        assert_eq!(
+            ((MAX_DEPTH + 1) * 18, DecodeReason::TooDeep),
+            (too_deep.offset, too_deep.reason)
+        );
+        // Built bit by bit, as a packet this deep would overflow the stack
+        let mut hostile = vec![];
+        for _ in 0..5000 {
+            hostile.extend([0, 0, 0, 0, 0, 0, 1]);
+            int_to_bits(&mut hostile, 1, 11)?;
+        }
+        hostile.extend(Packet::Literal(0, 1).encode(&mut count)?);
+        let hostile = error(&bits_to_hex(&hostile));
+        assert_eq!(DecodeReason::TooDeep, hostile.reason);
+
+        assert!(input_generator("D2FE28\n").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn expressions() -> Result<()> {
+        let expr: Expr = "max(3, 4*5) < sum(1,2,3)".parse()?;
+        use Expr::*;
+        assert_eq!(
+            Apply(
+                Operator::LessThan,
+                vec![
+                    Apply(
+                        Operator::Maximum,
+                        vec![
+                            Literal(3),
+                            Apply(Operator::Product, vec![Literal(4), Literal(5)])
+                        ]
+                    ),
+                    Apply(Operator::Sum, vec![Literal(1), Literal(2), Literal(3)])
+                ]
+            ),
+            expr
+        );
+        assert_eq!(0, eval(&expr)?);
+        assert_eq!(
+            0,
+            input_generator(&expr.compile(LengthType::Bits)?)?.value()?
+        );
+
+        for (source, value) in [
+            ("1 + 2 * 3", 7),
+            ("(1 + 2) * 3", 9),
+            ("eq(product(2, 3), 6)", 1),
+            ("gt(min(7, 8, 9), 6) + lt(1, 0)", 1),
+            ("2 * 3 == 3 + 3", 1),
+            ("sum()", 0),
+            ("18446744073709551615", u64::MAX),
+        ] {
+            let expr: Expr = source.parse()?;
+            assert_eq!(value, eval(&expr)?, "{}", source);
+            assert_eq!(
+                value,
+                part2(&input_generator(&expr.compile(LengthType::Bits)?)?)?,
+                "{}",
+                source
+            );
+        }
+
+        for bad in [
+            "",
+            "1 +",
+            "foo(1)",
+            "max(1 2)",
+            "(1",
+            "1 < 2 < 3",
+            "1 - 2",
+            "99999999999999999999",
+        ] {
+            assert!(bad.parse::<Expr>().is_err(), "{}", bad);
+        }
+        let max = Packet::Operator(
+            0,
+            Operator::Maximum,
+            (1..=3).map(|v| Packet::Literal(0, v)).collect(),
+        );
+        for (length_type, hex) in [("bits", "0C"), ("count", "0E")] {
+            let compiled = compile("max(1, 2, 3)", length_type.parse()?)?;
+            assert!(compiled.starts_with(hex), "{}", compiled);
+            assert_eq!(max, decode(&compiled)?);
+        }
+        assert!("both".parse::<LengthType>().is_err());
+        assert!(compile("1 +", LengthType::Bits).is_err());
+
+        let nest = |call: &str, depth| call.repeat(depth) + "1" + &")".repeat(depth);
+        let deepest = compile(&nest("sum(", MAX_DEPTH), LengthType::Count)?;
+        assert_eq!(1, part2(&input_generator(&deepest)?)?);
+        for too_deep in [
+            nest("sum(", MAX_DEPTH + 1),
+            nest("(", 100_000),
+            nest("(1 < 2 + 3 * ", 100),
+        ] {
+            assert!(compile(&too_deep, LengthType::Count).is_err());
+        }
+        assert!(eval(&"min()".parse()?).is_err());
+        assert!(eval(&"lt(1, 2, 3)".parse()?).is_err());
+        Ok(())
+    }
+
+    fn random_expr(rng: &mut Rng, depth: usize) -> String {
+        if depth == 0 || rng.chance(30) {
+            return rng.range(0, 20).to_string();
+        }
+        let mut arg = || format!("({})", random_expr(rng, depth - 1));
+        let (a, b, c) = (arg(), arg(), arg());
+        match rng.range(0, 6) {
+            0 => format!("{} + {} + {}", a, b, c),
+            1 => format!("{} * {}", a, b),
+            2 => format!("min({}, {}, {})", a, b, c),
+            3 => format!("max({})", a),
+            4 => format!("{} < {}", a, b),
+            5 => format!("gt({}, {})", a, b),
+            _ => format!("{} == {}", a, b),
+        }
+    }
+
+    #[test]
+    fn compiled_values() -> Result<()> {
+        let mut rng = Rng::new(22);
+        for _ in 0..200 {
+            let source = random_expr(&mut rng, 4);
+            let expr: Expr = source.parse()?;
+            let length_type = if rng.chance(50) {
+                LengthType::Bits
+            } else {
+                LengthType::Count
+            };
+            let hex = expr.compile(length_type)?;
+            assert_eq!(
+                eval(&expr)?,
+                input_generator(&hex)?.value()?,
+                "{} = {}",
+                source,
+                hex
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     fn smoke1() -> Result<()> {
         let input = input_generator("8A004A801A8002F478")?;
@@ -1,98 +1,63 @@
-use std::{collections::HashSet, fmt::Display};
+use std::fmt::Display;
 
 use anyhow::{Context, Result};
 use itertools::iproduct;
 
+use crate::Bitmap;
+
 type Coord = (i32, i32);
 
 #[derive(Debug, Clone)]
 struct Image {
     enhancer: Vec<bool>,
-    pixels: HashSet<Coord>,
-    base_pixel: bool,
-    min_x: i32,
-    max_x: i32,
-    min_y: i32,
-    max_y: i32,
+    pixels: Bitmap,
 }
 
 impl Image {
-    fn min_max(pixels: &HashSet<Coord>) -> (Coord, Coord) {
-        let mut x_min = i32::MAX;
-        let mut y_min = i32::MAX;
-        let mut x_max = i32::MIN;
-        let mut y_max = i32::MIN;
-
-        for pixel in pixels {
-            x_min = x_min.min(pixel.0);
-            y_min = y_min.min(pixel.1);
-            y_max = y_max.max(pixel.1);
-            x_max = x_max.max(pixel.0);
-        }
-        // println!("Limits: {:?}", ((x_min, y_min), (x_max, y_max)));
-        ((x_min, y_min), (x_max, y_max))
-    }
-
     fn neighborhood(coord: &Coord) -> impl Iterator<Item = Coord> {
         iproduct!(coord.1 - 1..=coord.1 + 1, coord.0 - 1..=coord.0 + 1).map(|c| (c.1, c.0))
     }
 
+    /// Everything outside the bounds sees only background pixels.
+    fn next_background(&self) -> bool {
+        if self.pixels.background() {
+            self.enhancer[511]
+        } else {
+            self.enhancer[0]
+        }
+    }
+
     fn enhance2(&mut self) {
-        let mut result = HashSet::new();
-        for x in self.min_x - 1..=self.max_x + 1 {
-            let mut idx = self.n2int(&mut Self::neighborhood(&(x, self.min_y - 2)));
-            for y in self.min_y - 1..=self.max_y + 1 {
-                let center = (x, y);
-                // idx >>= 3;
-                // let mut tmp_idx = 0;
-                for offset_x in x - 1..=x + 1 {
-                    idx <<= 1;
-                    if self.read_pixel(&(offset_x, y + 1)) {
-                        idx += 1;
+        let mut result = Bitmap::new(self.next_background());
+        if let Some(((min_x, min_y), (max_x, max_y))) = self.pixels.bounds() {
+            for x in min_x - 1..=max_x + 1 {
+                let mut idx = self.n2int(&mut Self::neighborhood(&(x, min_y - 2)));
+                for y in min_y - 1..=max_y + 1 {
+                    let center = (x, y);
+                    for offset_x in x - 1..=x + 1 {
+                        idx <<= 1;
+                        if self.read_pixel(&(offset_x, y + 1)) {
+                            idx += 1;
+                        }
                     }
-                }
-                // tmp_idx <<= 6;
-                idx &= 0x01ff;
-                // println!("\t=>{:?}", center);
-                // assert_eq!(idx, self.n2int(&mut Self::neighborhood(&center)));
-                if self.enhancer[idx] {
-                    result.insert(center);
+                    idx &= 0x01ff;
+                    // assert_eq!(idx, self.n2int(&mut Self::neighborhood(&center)));
+                    result.set(center, self.enhancer[idx]);
                 }
             }
         }
         self.pixels = result;
-        let ((min_x, min_y), (max_x, max_y)) = Self::min_max(&self.pixels);
-        self.min_x = min_x;
-        self.max_x = max_x;
-        self.min_y = min_y;
-        self.max_y = max_y;
-
-        if self.enhancer[0] && !self.enhancer[511] {
-            self.base_pixel = !self.base_pixel;
-        }
     }
 
     fn enhance(&mut self) {
-        let mut result = HashSet::new();
-        for center in iproduct!(
-            self.min_x - 1..=self.max_x + 1,
-            self.min_y - 1..=self.max_y + 1
-        ) {
-            let idx = self.n2int(&mut Self::neighborhood(&center));
-            if self.enhancer[idx] {
-                result.insert(center.to_owned());
+        let mut result = Bitmap::new(self.next_background());
+        if let Some(((min_x, min_y), (max_x, max_y))) = self.pixels.bounds() {
+            for center in iproduct!(min_x - 1..=max_x + 1, min_y - 1..=max_y + 1) {
+                let idx = self.n2int(&mut Self::neighborhood(&center));
+                result.set(center, self.enhancer[idx]);
             }
         }
         self.pixels = result;
-        let ((min_x, min_y), (max_x, max_y)) = Self::min_max(&self.pixels);
-        self.min_x = min_x;
-        self.max_x = max_x;
-        self.min_y = min_y;
-        self.max_y = max_y;
-
-        if self.enhancer[0] && !self.enhancer[511] {
-            self.base_pixel = !self.base_pixel;
-        }
     }
 
     fn n2int(&self, iter: &mut impl Iterator<Item = Coord>) -> usize {
@@ -108,49 +73,24 @@ impl Image {
     }
 
     fn read_pixel(&self, coord: &Coord) -> bool {
-        #[allow(clippy::if_same_then_else)]
-        if self.base_pixel
-            && (coord.0 < self.min_x
-                || coord.0 > self.max_x
-                || coord.1 < self.min_y
-                || coord.1 > self.max_y)
-        {
-            return true;
-        } else if self.pixels.contains(coord) {
-            return true;
-        }
-        false
+        self.pixels.get(coord)
     }
 
-    fn new(enhancer: Vec<bool>, pixels: HashSet<Coord>) -> Self {
+    fn lit_count(&self) -> Result<usize> {
+        self.pixels
+            .lit_count()
+            .context("Infinitely many pixels are lit")
+    }
+
+    fn new(enhancer: Vec<bool>, pixels: Bitmap) -> Self {
         // println!("{:?}", pixels);
-        let ((min_x, min_y), (max_x, max_y)) = Self::min_max(&pixels);
-        let base_pixel = false;
-        Self {
-            enhancer,
-            pixels,
-            base_pixel,
-            min_x,
-            max_x,
-            min_y,
-            max_y,
-        }
+        Self { enhancer, pixels }
     }
 }
 
 impl Display for Image {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for y in self.min_y..=self.max_y {
-            for x in self.min_x..=self.max_x {
-                if self.pixels.contains(&(x, y)) {
-                    write!(f, "#")?;
-                } else {
-                    write!(f, ".")?;
-                }
-            }
-            writeln!(f)?;
-        }
-        Ok(())
+        write!(f, "{}", self.pixels)
     }
 }
 
@@ -166,12 +106,10 @@ fn input_generator(input: &str) -> Result<Image> {
 
     lines.next(); // Blank line
 
-    let mut pixels = HashSet::new();
+    let mut pixels = Bitmap::new(false);
     for (y, l) in lines.enumerate() {
         for (x, c) in l.chars().enumerate() {
-            if c == '#' {
-                pixels.insert((x as i32, y as i32));
-            }
+            pixels.set((x as i32, y as i32), c == '#');
         }
     }
     Ok(Image::new(enhancer, pixels))
@@ -187,7 +125,7 @@ fn part1(input: &Image) -> Result<usize> {
     // println!("{}", image);
     image.enhance();
     // println!("{}", image);
-    Ok(image.pixels.len())
+    image.lit_count()
 }
 
 #[aoc(day20, part2)]
@@ -196,7 +134,7 @@ fn part2(input: &Image) -> Result<usize> {
     for _ in 0..50 {
         image.enhance();
     }
-    Ok(image.pixels.len())
+    image.lit_count()
 }
 
 #[aoc(day20, part1, window)]
@@ -209,7 +147,7 @@ fn part1_window(input: &Image) -> Result<usize> {
     // println!("{}", image);
     image.enhance2();
     // println!("{}", image);
-    Ok(image.pixels.len())
+    image.lit_count()
 }
 
 #[aoc(day20, part2, window)]
@@ -218,12 +156,13 @@ fn part2_window(input: &Image) -> Result<usize> {
     for _ in 0..50 {
         image.enhance2();
     }
-    Ok(image.pixels.len())
+    image.lit_count()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::differential::{check_agreement, solver, without_each, Rng};
 
     const SMOKE: &str = "..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..###..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###.######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#..#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.##.#......#..#...##.#.##..#...##.#.##..###.#......#.#.......#.#.#.####.###.##...#.....####.#..#..#.##.#....##..#.####....##...##..#...#......#.#.......#.......##..####..#...#.#.#...##..#.#..###..#####........#..####......#..#
 
@@ -249,6 +188,22 @@ mod tests {
         assert_eq!(None, grid.next());
     }
 
+    #[test]
+    fn flashing_background() -> Result<()> {
+        let (enhancer, picture) = SMOKE.split_once('\n').unwrap();
+        let enhancer = format!("#{}.", &enhancer[1..enhancer.len() - 1]);
+        let input = input_generator(&format!("{}\n{}", enhancer, picture))?;
+        let mut image = input.to_owned();
+        let mut window = input.to_owned();
+        image.enhance();
+        window.enhance2();
+        assert!(image.pixels.background());
+        assert!(image.lit_count().is_err());
+        assert_eq!(image.pixels, window.pixels);
+        assert_eq!(part1(&input)?, part1_window(&input)?);
+        Ok(())
+    }
+
     #[test]
     fn smoke1() -> Result<()> {
         let input = input_generator(SMOKE)?;
@@ -276,4 +231,59 @@ mod tests {
         assert_eq!(3351, part2_window(&input)?);
         Ok(())
     }
+
+    #[test]
+    fn variants_agree() {
+        check_agreement(
+            20,
+            |rng: &mut Rng| {
+                let mut enhancer: Vec<bool> = (0..512).map(|_| rng.chance(50)).collect();
+                // Keep the lit pixel count finite
+                if enhancer[0] {
+                    enhancer[511] = false;
+                }
+                let width = rng.range(1, 6);
+                let pixels: Vec<Vec<bool>> = (0..rng.range(1, 6))
+                    .map(|_| (0..width).map(|_| rng.chance(40)).collect())
+                    .collect();
+                (enhancer, pixels)
+            },
+            |(enhancer, pixels)| {
+                let mut result = vec![];
+                if pixels.len() > 1 {
+                    result.extend(
+                        without_each(pixels)
+                            .into_iter()
+                            .map(|p| (enhancer.clone(), p)),
+                    );
+                }
+                for (i, lit) in enhancer.iter().enumerate() {
+                    if *lit && i != 0 {
+                        let mut darker = enhancer.clone();
+                        darker[i] = false;
+                        result.push((darker, pixels.clone()));
+                    }
+                }
+                result
+            },
+            |(enhancer, pixels)| {
+                let row = |bits: &Vec<bool>| -> String {
+                    bits.iter().map(|b| if *b { '#' } else { '.' }).collect()
+                };
+                let mut result = row(enhancer);
+                result.push('\n');
+                for pixel_row in pixels {
+                    result.push('\n');
+                    result.push_str(&row(pixel_row));
+                }
+                result
+            },
+            &[
+                solver("part1", &|s| Ok(part1(&input_generator(s)?)?.to_string())),
+                solver("part1_window", &|s| {
+                    Ok(part1_window(&input_generator(s)?)?.to_string())
+                }),
+            ],
+        );
+    }
 }
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
 
 trait Die {
     fn roll(&mut self) -> u16;
@@ -34,9 +36,9 @@ struct DiracGame<D: Die> {
 }
 
 impl<D: Die> DiracGame<D> {
-    fn new(player1: u8, player2: u8, die: D) -> Self {
-        let players = vec![player1, player2];
-        let scores = vec![0; 2];
+    fn new(players: &[u8], die: D) -> Self {
+        let players = players.to_vec();
+        let scores = vec![0; players.len()];
         Self {
             players,
             die,
@@ -58,69 +60,115 @@ impl<D: Die> DiracGame<D> {
     }
 }
 
-#[derive(Debug, Clone, Copy, Hash, Default, PartialEq, Eq)]
-struct QuantumGameState {
-    players: [u8; 2],
-    scores: [u8; 2],
-}
-
+/// Players roll independently, so rather than tracking every combination
+/// of positions and scores, each player's universes are tracked apart and
+/// multiplied together when someone wins. Universe counts pass `u64` from
+/// three players on, so they are `u128` and checked.
 struct QuantumGame {
-    states: HashMap<QuantumGameState, u64>,
-    wins: [u64; 2],
+    /// For each player, the universes where they have not won yet, by
+    /// position and score.
+    players: Vec<HashMap<(u8, u8), u128>>,
+    wins: Vec<u128>,
     turn: usize,
 }
 
-const QUANTUM_SPLITS: [(u8, u64); 7] = [(3, 1), (4, 3), (5, 6), (6, 7), (7, 6), (8, 3), (9, 1)];
+const QUANTUM_SPLITS: [(u8, u128); 7] = [(3, 1), (4, 3), (5, 6), (6, 7), (7, 6), (8, 3), (9, 1)];
 
 impl QuantumGame {
     fn new(input: &Input) -> Self {
-        let state = QuantumGameState {
-            players: [input.0, input.1],
-            scores: [0; 2],
-        };
-        let mut states = HashMap::new();
-        states.insert(state, 1u64);
-        let wins = [0; 2];
+        let players = input
+            .iter()
+            .map(|position| HashMap::from([((*position, 0), 1)]))
+            .collect();
+        let wins = vec![0; input.len()];
         let turn = 0;
-        Self { states, wins, turn }
+        Self {
+            players,
+            wins,
+            turn,
+        }
     }
 
-    fn go(&mut self) -> usize {
-        let mut result: HashMap<QuantumGameState, u64> = HashMap::new();
-        for (src, count) in &self.states {
+    /// Plays one turn, returning how many distinct player states are left,
+    /// or 0 once every universe has a winner.
+    fn go(&mut self) -> Result<usize> {
+        let overflow = "Universe count overflow";
+        // Universes where none of the other players has won yet
+        let mut others: u128 = 1;
+        for (idx, player) in self.players.iter().enumerate() {
+            if idx != self.turn {
+                let undecided = player
+                    .values()
+                    .try_fold(0u128, |acc, count| acc.checked_add(*count))
+                    .context(overflow)?;
+                others = others.checked_mul(undecided).context(overflow)?;
+            }
+        }
+
+        let mut result: HashMap<(u8, u8), u128> = HashMap::new();
+        for ((position, score), count) in &self.players[self.turn] {
             for split in QUANTUM_SPLITS {
-                let mut dest = src.to_owned();
-                dest.players[self.turn] += split.0;
-                if dest.players[self.turn] > 10 {
-                    dest.players[self.turn] -= 10;
+                let mut position = position + split.0;
+                if position > 10 {
+                    position -= 10;
                 }
-                dest.scores[self.turn] += dest.players[self.turn];
-                if dest.scores[self.turn] >= 21 {
-                    self.wins[self.turn] += count * split.1;
+                let score = score + position;
+                let universes = count.checked_mul(split.1).context(overflow)?;
+                if score >= 21 {
+                    let won = universes.checked_mul(others).context(overflow)?;
+                    self.wins[self.turn] =
+                        self.wins[self.turn].checked_add(won).context(overflow)?;
                 } else {
-                    *result.entry(dest).or_default() += count * split.1;
+                    let entry = result.entry((position, score)).or_default();
+                    *entry = entry.checked_add(universes).context(overflow)?;
                 }
             }
         }
-        // if result.len() < 50 {
-        //     println!("\t {:?}", result);
-        // }
-        self.turn = 1 - self.turn;
-        self.states = result;
+        self.players[self.turn] = result;
+        self.turn = (self.turn + 1) % self.players.len();
 
-        self.states.len()
+        if self.players.iter().any(HashMap::is_empty) {
+            Ok(0)
+        } else {
+            Ok(self.players.iter().map(HashMap::len).sum())
+        }
     }
 }
-type Input = (u8, u8);
+type Input = Vec<u8>;
 
 #[aoc_generator(day21)]
-fn input_generator(_input: &str) -> Result<Input> {
-    Ok((9, 4))
+fn input_generator(input: &str) -> Result<Input> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new("^Player (\\d+) starting position: (\\d+)$").unwrap();
+    };
+    let mut result = vec![];
+    for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let captures = RE
+            .captures(line)
+            .with_context(|| format!("Bad line: {}", line))?;
+        let player: usize = captures.get(1).unwrap().as_str().parse()?;
+        let position: u8 = captures.get(2).unwrap().as_str().parse()?;
+        ensure!(
+            player == result.len() + 1,
+            "Expected player {} but found {}",
+            result.len() + 1,
+            player
+        );
+        ensure!(
+            (1..=10).contains(&position),
+            "Invalid position {} for player {}",
+            position,
+            player
+        );
+        result.push(position);
+    }
+    ensure!(!result.is_empty(), "No players");
+    Ok(result)
 }
 
 #[aoc(day21, part1)]
 fn part1(input: &Input) -> Result<u64> {
-    let mut game = DiracGame::new(input.0, input.1, DeterministicDie::default());
+    let mut game = DiracGame::new(input, DeterministicDie::default());
     // println!("{:?}", game);
 
     while !game.go() {
@@ -128,15 +176,15 @@ fn part1(input: &Input) -> Result<u64> {
     }
     // println!("{:?}", game);
 
-    let loser_score = game.scores[game.turn];
+    let loser_score = game.scores.iter().copied().min().context("No players?")?;
     Ok(loser_score * game.die.roll_cnt())
 }
 
 #[aoc(day21, part2)]
-fn part2(input: &Input) -> Result<u64> {
+fn part2(input: &Input) -> Result<u128> {
     let mut game = QuantumGame::new(input);
     loop {
-        let states = game.go();
+        let states = game.go()?;
         println!("Distinct states: {}", states);
         if states == 0 {
             break;
@@ -149,15 +197,67 @@ fn part2(input: &Input) -> Result<u64> {
 mod tests {
     use super::*;
 
+    const SMOKE: &str = "Player 1 starting position: 4
+Player 2 starting position: 8";
+
+    #[test]
+    fn parse() -> Result<()> {
+        assert_eq!(vec![4, 8], input_generator(SMOKE)?);
+        assert_eq!(
+            vec![4, 8, 10],
+            input_generator(&format!("{}\nPlayer 3 starting position: 10", SMOKE))?
+        );
+        assert!(input_generator("Player 2 starting position: 4").is_err());
+        assert!(input_generator("Player 1 starting position: 11").is_err());
+        assert!(input_generator("Player 1 starts at 4").is_err());
+        assert!(input_generator("").is_err());
+        Ok(())
+    }
+
     #[test]
     fn smoke1() -> Result<()> {
-        assert_eq!(739785, part1(&(4, 8))?);
+        let input = input_generator(SMOKE)?;
+        assert_eq!(739785, part1(&input)?);
         Ok(())
     }
 
     #[test]
     fn smoke2() -> Result<()> {
-        assert_eq!(444356092776315u64, part2(&(4, 8))?);
+        let input = input_generator(SMOKE)?;
+        assert_eq!(444356092776315, part2(&input)?);
+        Ok(())
+    }
+
+    const THREE: &str = "Player 1 starting position: 4
+Player 2 starting position: 8
+Player 3 starting position: 10";
+
+    #[test]
+    fn three_players1() -> Result<()> {
+        let input = input_generator(THREE)?;
+        let mut game = DiracGame::new(&input, DeterministicDie::default());
+        while !game.go() {}
+        // Player 3 wins and player 2 loses
+        assert_eq!(vec![996, 987, 1002], game.scores);
+        assert_eq!(1377, game.die.roll_cnt());
+        assert_eq!(987 * 1377, part1(&input)?);
+        Ok(())
+    }
+
+    #[test]
+    fn three_players2() -> Result<()> {
+        let input = input_generator(THREE)?;
+        let mut game = QuantumGame::new(&input);
+        while game.go()? > 0 {}
+        assert_eq!(
+            vec![
+                553838663218932375417,
+                459601709757886369695,
+                481422017007976331589
+            ],
+            game.wins
+        );
+        assert_eq!(553838663218932375417, part2(&input)?);
         Ok(())
     }
 }
@@ -341,6 +341,7 @@ fn part2(input: &[Cube]) -> Result<i64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::differential::{check_agreement, solver, without_each, Rng};
 
     const SMOKE: &str = "on x=-20..26,y=-36..17,z=-47..7
     on x=-20..33,y=-21..23,z=-26..28
@@ -465,4 +466,54 @@ on x=10..10,y=10..10,z=10..10",
         assert_eq!(474140, part1_shattered(&input)?);
         Ok(())
     }
+
+    #[test]
+    fn variants_agree() {
+        check_agreement(
+            8,
+            |rng: &mut Rng| {
+                let mut steps = vec![];
+                for _ in 0..rng.range(1, 5) {
+                    let on = rng.chance(60);
+                    let mut ranges = [(0, 0); 3];
+                    for range in &mut ranges {
+                        let low = rng.range(-60, 50);
+                        *range = (low, low + rng.range(0, 30));
+                    }
+                    steps.push((on, ranges));
+                }
+                steps
+            },
+            |steps| {
+                without_each(steps)
+                    .into_iter()
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            },
+            |steps| {
+                steps
+                    .iter()
+                    .map(|(on, [x, y, z])| {
+                        format!(
+                            "{} x={}..{},y={}..{},z={}..{}",
+                            if *on { "on" } else { "off" },
+                            x.0,
+                            x.1,
+                            y.0,
+                            y.1,
+                            z.0,
+                            z.1
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            },
+            &[
+                solver("part1", &|s| Ok(part1(&input_generator(s)?)?.to_string())),
+                solver("part1_shattered", &|s| {
+                    Ok(part1_shattered(&input_generator(s)?)?.to_string())
+                }),
+            ],
+        );
+    }
 }
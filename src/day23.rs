@@ -1,530 +1,399 @@
 use std::{
-    collections::{BinaryHeap, HashMap, HashSet},
+    collections::{HashMap, HashSet},
     fmt::Display,
+    hash::Hash,
+    rc::Rc,
+    str::FromStr,
 };
 
-use anyhow::Result;
-use lazy_static::lazy_static;
-
-type Input = [[char; 2]; 4];
-
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-struct World([Option<char>; 19]);
-
-impl From<&Input> for World {
-    fn from(input: &Input) -> Self {
-        World([
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            Some(input[0][0]),
-            Some(input[0][1]),
-            Some(input[1][0]),
-            Some(input[1][1]),
-            Some(input[2][0]),
-            Some(input[2][1]),
-            Some(input[3][0]),
-            Some(input[3][1]),
-        ])
-    }
-}
+use anyhow::{bail, ensure, Context, Result};
 
-fn disp_char(val: Option<char>) -> char {
-    if let Some(pod) = val {
-        pod
-    } else {
-        '.'
-    }
+use crate::{astar, dijkstra, ShortestPath};
+
+/// Rows pushed into the middle of every room when the diagram is unfolded for part 2.
+const FOLDED_ROWS: [[char; 4]; 2] = [['D', 'C', 'B', 'A'], ['D', 'B', 'A', 'C']];
+
+/// A parsed burrow diagram: the hallway and each room listed from the hallway down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Diagram {
+    hallway: Vec<Option<char>>,
+    rooms: Vec<Vec<Option<char>>>,
 }
 
-fn insert_if_less(hash: &mut HashMap<World, i32>, world: World, cost: i32) {
-    if let Some(old) = hash.get_mut(&world) {
-        *old = cost.min(*old);
-    } else {
-        hash.insert(world, cost);
+fn parse_cell(c: char, rooms: usize) -> Result<Option<char>> {
+    match c {
+        '.' => Ok(None),
+        'A'..='Z' if ((c as u8 - b'A') as usize) < rooms => Ok(Some(c)),
+        _ => bail!("Unknown pod: {}", c),
     }
 }
 
-impl Display for World {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "#############")?;
-        write!(f, "#")?;
-        for idx in 0..=10 {
-            write!(f, "{}", disp_char(self.0[idx]))?;
+impl FromStr for Diagram {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+        ensure!(lines.len() >= 4, "Burrow is too short");
+        let hallway = lines[1]
+            .strip_prefix('#')
+            .and_then(|l| l.strip_suffix('#'))
+            .filter(|l| l.len() >= 5 && l.len() % 2 == 1)
+            .with_context(|| format!("Bad hallway: {}", lines[1]))?;
+        let room_count = (hallway.len() - 3) / 2;
+        ensure!(
+            lines[0] == "#".repeat(hallway.len() + 2),
+            "Bad top wall: {}",
+            lines[0]
+        );
+        let hallway = hallway
+            .chars()
+            .map(|c| parse_cell(c, room_count))
+            .collect::<Result<_>>()?;
+        let bottom = lines[lines.len() - 1];
+        ensure!(
+            bottom == "#".repeat(room_count * 2 + 1),
+            "Bad bottom wall: {}",
+            bottom
+        );
+
+        let mut rooms = vec![vec![]; room_count];
+        for (depth, line) in lines[2..lines.len() - 1].iter().enumerate() {
+            let row = if depth == 0 {
+                line.strip_prefix("##").and_then(|l| l.strip_suffix("##"))
+            } else {
+                Some(*line)
+            }
+            .with_context(|| format!("Bad room row: {}", line))?;
+            let cells: Vec<char> = row.chars().collect();
+            ensure!(
+                cells.len() == room_count * 2 + 1 && cells.iter().step_by(2).all(|c| *c == '#'),
+                "Bad room row: {}",
+                line
+            );
+            for (room, cell) in cells.iter().skip(1).step_by(2).enumerate() {
+                rooms[room].push(parse_cell(*cell, room_count)?);
+            }
         }
-        writeln!(f, "#")?;
-        write!(f, "###")?;
-        write!(f, "{}", disp_char(self.0[11]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[13]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[15]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[17]))?;
-        writeln!(f, "###")?;
-        write!(f, "  #")?;
-        write!(f, "{}", disp_char(self.0[12]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[14]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[16]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[18]))?;
-        writeln!(f, "#")?;
-        writeln!(f, "  #########")?;
-
-        Ok(())
+        let result = Diagram { hallway, rooms };
+        for pod in (b'A'..).take(room_count).map(char::from) {
+            let count = result.cells().filter(|c| *c == Some(pod)).count();
+            ensure!(
+                count == result.depth(),
+                "Expected {} of pod {} but found {}",
+                result.depth(),
+                pod,
+                count
+            );
+        }
+        ensure!(
+            result.max_energy().is_some(),
+            "Energy for {} rooms of depth {} could overflow",
+            room_count,
+            result.depth()
+        );
+        Ok(result)
     }
 }
 
-impl World {
-    fn step_pod(&self, src: usize, dst: usize) -> World {
-        let mut result = self.to_owned();
-        result.0[dst] = result.0[src];
-        result.0[src] = None;
-        result
-    }
-
-    fn moves(&self, idx: usize) -> HashMap<World, i32> {
-        let mut result = HashMap::new();
-        let pod = self.0[idx];
-        if pod.is_none() {
-            return result;
+impl Diagram {
+    fn depth(&self) -> usize {
+        self.rooms[0].len()
+    }
+
+    /// A bound on the energy the search may reach. Every pod moves at most
+    /// twice, each time no further than the hallway plus two rooms, and the
+    /// search looks up to one move past the cheapest solution plus a heuristic
+    /// of at most the same again.
+    fn max_energy(&self) -> Option<i64> {
+        let depth = self.depth() as i64;
+        let steps = 2 * (self.hallway.len() as i64 + 2 * depth);
+        (0..self.rooms.len() as u32)
+            .try_fold(0i64, |acc, room| {
+                10i64
+                    .checked_pow(room)?
+                    .checked_mul(steps * depth)?
+                    .checked_add(acc)
+            })?
+            .checked_mul(4)
+    }
+
+    fn unfold(&self) -> Result<Self> {
+        ensure!(
+            self.rooms.len() == 4,
+            "Only four room burrows can be unfolded"
+        );
+        let mut result = self.clone();
+        for (room, contents) in result.rooms.iter_mut().enumerate() {
+            for (offset, row) in FOLDED_ROWS.iter().enumerate() {
+                contents.insert(1 + offset, Some(row[room]));
+            }
         }
-        let pod = pod.unwrap();
+        Ok(result)
+    }
 
-        // If we are in the correct place, we do nothing
-        let destination_room = Self::dest_room(pod);
+    /// Lays out the hallway followed by each room in turn.
+    fn cells(&self) -> impl Iterator<Item = Option<char>> + '_ {
+        self.hallway
+            .iter()
+            .chain(self.rooms.iter().flatten())
+            .copied()
+    }
+}
 
-        if Self::room_num(idx) == destination_room
-            && (destination_room != Self::room_num(idx + 1) || self.0[idx + 1] == Some(pod))
-        {
-            return result;
-        }
+/// The fixed shape of a burrow. Cells are numbered along the hallway first
+/// and then through each room from the hallway down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Layout {
+    rooms: usize,
+    depth: usize,
+    adjacency: Vec<Vec<usize>>,
+}
 
-        let step_cost = match pod {
-            'A' => 1,
-            'B' => 10,
-            'C' => 100,
-            'D' => 1000,
-            _ => panic!("Bad character"),
+impl Layout {
+    fn new(rooms: usize, depth: usize) -> Self {
+        let hallway = rooms * 2 + 3;
+        let mut adjacency = vec![vec![]; hallway + rooms * depth];
+        let mut connect = |a: usize, b: usize| {
+            adjacency[a].push(b);
+            adjacency[b].push(a);
         };
-        let can_stop_in_hall = !Self::is_hallway(idx);
-
-        let mut possibilities = vec![(idx, self.to_owned(), 0)];
-        let mut visited = HashSet::new();
-        while !possibilities.is_empty() {
-            let (idx, current_world, cost) = possibilities.pop().unwrap();
-            if !visited.insert(current_world.to_owned()) {
-                continue;
-            }
-            // println!("Considering at cost {}", cost);
-            // println!("{}", current_world);
-            for neighbor in &ADJACENCY[idx] {
-                if current_world.0[*neighbor].is_some() {
-                    continue;
-                }
-
-                // If I'm in a hallway, I can step elsewhere in the hallway or into my room
-                if Self::is_hallway(idx) {
-                    if Self::is_hallway(*neighbor) {
-                        let dest = current_world.step_pod(idx, *neighbor);
-                        let valid_stop = can_stop_in_hall && !Self::is_doorway(*neighbor);
-                        if valid_stop {
-                            insert_if_less(&mut result, dest.to_owned(), cost + step_cost);
-                        }
-                        possibilities.push((*neighbor, dest, cost + step_cost));
-                    } else if Self::room_num(*neighbor) == destination_room {
-                        // I can enter, but can I go all the way? If I can, then I should
-                        if let Some(resident) = current_world.0[neighbor + 1] {
-                            if resident == pod {
-                                // This is my fellow pod
-                                let dest = current_world.step_pod(idx, *neighbor);
-                                result.clear();
-                                // insert_if_less(&mut result, dest, cost+step_cost);
-                                result.insert(dest, cost + step_cost);
-                                return result;
-                            }
-                        } else {
-                            // Room is empty!
-                            let dest = current_world.step_pod(idx, neighbor + 1);
-                            // insert_if_less(&mut result, dest, cost+step_cost+step_cost);
-                            result.clear();
-                            result.insert(dest, cost + step_cost + step_cost);
-                            return result;
-                        }
-                    }
-                } else {
-                    // I'm in a room that isn't my own. There are no valid stopping points from here
-                    let dest = current_world.step_pod(idx, *neighbor);
-                    possibilities.push((*neighbor, dest, cost + step_cost));
-                }
+        for idx in 1..hallway {
+            connect(idx - 1, idx);
+        }
+        for room in 0..rooms {
+            let top = hallway + room * depth;
+            connect(2 + room * 2, top);
+            for idx in top + 1..top + depth {
+                connect(idx - 1, idx);
             }
         }
+        Self {
+            rooms,
+            depth,
+            adjacency,
+        }
+    }
 
-        result
+    fn hallway_len(&self) -> usize {
+        self.rooms * 2 + 3
+    }
+
+    fn len(&self) -> usize {
+        self.adjacency.len()
     }
 
-    fn is_hallway(idx: usize) -> bool {
-        idx <= 0xa
+    fn is_hallway(&self, idx: usize) -> bool {
+        idx < self.hallway_len()
     }
 
-    fn is_doorway(idx: usize) -> bool {
-        ADJACENCY[idx].len() > 2
+    fn is_doorway(&self, idx: usize) -> bool {
+        self.adjacency[idx].len() > 2
     }
 
-    fn room_num(idx: usize) -> u8 {
-        if idx < 0xb {
-            100
+    fn room_num(&self, idx: usize) -> Option<usize> {
+        if self.is_hallway(idx) {
+            None
         } else {
-            (idx as u8 - 0xb) / 2
+            Some((idx - self.hallway_len()) / self.depth)
         }
-        // assert!(!Self::is_hallway(idx));
     }
 
-    fn dest_room(pod: char) -> u8 {
-        pod as u8 - b'A'
+    fn door(&self, room: usize) -> usize {
+        2 + room * 2
     }
 
-    fn win(&self) -> bool {
-        for idx in 11..=18 {
-            // println!("{:?} at {} dest is {} current is {}", self.0[idx], idx, Self::dest_room(self.0[idx].unwrap()), Self::room_num(idx));
-            if let Some(pod) = self.0[idx] {
-                if Self::dest_room(pod) != Self::room_num(idx) {
-                    return false;
-                }
-            } else {
-                return false;
-            }
-        }
-        true
+    fn depth_of(&self, idx: usize) -> usize {
+        (idx - self.hallway_len()) % self.depth
     }
-}
-
-lazy_static! {
-    static ref ADJACENCY: Vec<Vec<usize>> = vec![
-        vec![1],
-        vec![0, 2],
-        vec![1, 11, 3],
-        vec![2, 4],
-        vec![3, 13, 5],
-        vec![4, 6],
-        vec![5, 15, 7],
-        vec![6, 8],
-        vec![7, 17, 9],
-        vec![8, 10],
-        vec![9],
-        vec![2, 12],
-        vec![11],
-        vec![4, 14],
-        vec![13],
-        vec![6, 16],
-        vec![15],
-        vec![8, 18],
-        vec![17]
-    ];
-}
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-struct HeapElement {
-    world: World,
-    cost: i32,
-}
+    fn room_cell(&self, room: usize, depth: usize) -> usize {
+        self.hallway_len() + room * self.depth + depth
+    }
 
-impl HeapElement {
-    fn new(world: World, cost: i32) -> Self {
-        HeapElement { world, cost }
+    fn dest_room(pod: char) -> usize {
+        (pod as u8 - b'A') as usize
     }
-}
 
-impl From<(World, i32)> for HeapElement {
-    fn from(tuple: (World, i32)) -> Self {
-        HeapElement::new(tuple.0, tuple.1)
+    fn step_cost(pod: char) -> i64 {
+        10i64.pow(Self::dest_room(pod) as u32)
     }
 }
 
-impl PartialOrd for HeapElement {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
+#[derive(Debug, Clone)]
+struct World {
+    layout: Rc<Layout>,
+    cells: Vec<Option<char>>,
 }
 
-impl Ord for HeapElement {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.cost.cmp(&self.cost)
+// Worlds only ever meet others sharing their layout, so the cells alone identify them.
+impl PartialEq for World {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells
     }
 }
 
-fn solve(world: World) -> i32 {
-    let mut queue = BinaryHeap::new();
-    let mut visited = HashSet::new();
-    queue.push(HeapElement::new(world, 0));
-    let mut cheapest = i32::MAX;
-    while !queue.is_empty() {
-        let current = queue.pop().unwrap();
-        if current.cost > cheapest {
-            return cheapest;
-        }
-        if !visited.insert(current.world.to_owned()) {
-            continue;
-        }
-        // println!("Visited {} worlds with {} in queue and current cost is {}", visited.len(), queue.len(), current.cost);
-        for idx in 0..=18 {
-            for next in current.world.moves(idx) {
-                if next.0.win() {
-                    // println!("Found solution with cost {}", next.1 + current.cost);
-                    cheapest = cheapest.min(next.1 + current.cost);
-                } else {
-                    queue.push(HeapElement::new(next.0, next.1 + current.cost));
-                }
-            }
-        }
+impl Eq for World {}
+
+impl Hash for World {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.cells.hash(state);
     }
-    cheapest
 }
 
-fn solve2(world: World2) -> i32 {
-    let mut queue = BinaryHeap::new();
-    let mut visited = HashSet::new();
-    queue.push(HeapElement2::new(world, 0));
-    let mut cheapest = i32::MAX;
-    while !queue.is_empty() {
-        let current = queue.pop().unwrap();
-        if current.cost > cheapest {
-            return cheapest;
-        }
-        if !visited.insert(current.world.to_owned()) {
-            continue;
-        }
-        // println!("Visited {} worlds with {} in queue and current cost is {}", visited.len(), queue.len(), current.cost);
-        // if visited.len() < 40 {
-        // println!("Considering \n{}", current.world);
-        // }
-        for idx in 0..27 {
-            for next in current.world.moves(idx) {
-                let next_cost = next.1 + current.cost;
-                if next.0.win() {
-                    // println!("Found solution with cost {}", next.1 + current.cost);
-                    cheapest = cheapest.min(next_cost);
-                } else if next_cost < cheapest {
-                    queue.push(HeapElement2::new(next.0, next.1 + current.cost));
-                }
-            }
-        }
+impl From<&Diagram> for World {
+    fn from(diagram: &Diagram) -> Self {
+        let layout = Rc::new(Layout::new(diagram.rooms.len(), diagram.depth()));
+        let cells = diagram.cells().collect();
+        World { layout, cells }
     }
-    cheapest
 }
 
-type Input2 = [[char; 4]; 4];
-
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-struct World2([Option<char>; 27]);
-
-impl From<&Input2> for World2 {
-    fn from(input: &Input2) -> Self {
-        World2([
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            Some(input[0][0]),
-            Some(input[0][1]),
-            Some(input[0][2]),
-            Some(input[0][3]),
-            Some(input[1][0]),
-            Some(input[1][1]),
-            Some(input[1][2]),
-            Some(input[1][3]),
-            Some(input[2][0]),
-            Some(input[2][1]),
-            Some(input[2][2]),
-            Some(input[2][3]),
-            Some(input[3][0]),
-            Some(input[3][1]),
-            Some(input[3][2]),
-            Some(input[3][3]),
-        ])
+impl FromStr for World {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let diagram: Diagram = s.parse()?;
+        Ok((&diagram).into())
     }
 }
 
-fn insert_if_less2(hash: &mut HashMap<World2, i32>, world: World2, cost: i32) {
-    if let Some(old) = hash.get_mut(&world) {
-        *old = cost.min(*old);
-    } else {
-        hash.insert(world, cost);
-    }
+fn disp_char(val: Option<char>) -> char {
+    val.unwrap_or('.')
 }
 
-impl Display for World2 {
+impl Display for World {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "#############")?;
+        let layout = &self.layout;
+        writeln!(f, "{}", "#".repeat(layout.hallway_len() + 2))?;
         write!(f, "#")?;
-        for idx in 0..=10 {
-            write!(f, "{}", disp_char(self.0[idx]))?;
+        for idx in 0..layout.hallway_len() {
+            write!(f, "{}", disp_char(self.cells[idx]))?;
         }
         writeln!(f, "#")?;
-        write!(f, "###")?;
-        write!(f, "{}", disp_char(self.0[11]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[15]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[19]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[23]))?;
-        writeln!(f, "###")?;
-        write!(f, "  #")?;
-        write!(f, "{}", disp_char(self.0[12]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[16]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[20]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[24]))?;
-        writeln!(f, "#")?;
-        write!(f, "  #")?;
-        write!(f, "{}", disp_char(self.0[13]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[17]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[21]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[25]))?;
-        writeln!(f, "#")?;
-        write!(f, "  #")?;
-        write!(f, "{}", disp_char(self.0[14]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[18]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[22]))?;
-        write!(f, "#")?;
-        write!(f, "{}", disp_char(self.0[26]))?;
-        writeln!(f, "#")?;
-        writeln!(f, "  #########")?;
+        for depth in 0..layout.depth {
+            write!(f, "{}", if depth == 0 { "###" } else { "  #" })?;
+            for room in 0..layout.rooms {
+                write!(
+                    f,
+                    "{}#",
+                    disp_char(self.cells[layout.room_cell(room, depth)])
+                )?;
+            }
+            writeln!(f, "{}", if depth == 0 { "##" } else { "" })?;
+        }
+        writeln!(f, "  {}", "#".repeat(layout.rooms * 2 + 1))?;
 
         Ok(())
     }
 }
 
-impl World2 {
-    fn step_pod(&self, src: usize, dst: usize) -> World2 {
+impl World {
+    fn step_pod(&self, src: usize, dst: usize) -> World {
         let mut result = self.to_owned();
-        result.0[dst] = result.0[src];
-        result.0[src] = None;
+        result.cells[dst] = result.cells[src];
+        result.cells[src] = None;
         result
     }
 
-    fn moves(&self, idx: usize) -> HashMap<World2, i32> {
-        let mut result = HashMap::new();
-        let pod = self.0[idx];
-        if pod.is_none() {
-            return result;
+    /// The deepest free cell of `room`, provided it holds nothing but `pod`s.
+    fn open_slot(&self, room: usize, pod: char) -> Option<usize> {
+        let mut result = None;
+        for depth in 0..self.layout.depth {
+            match self.cells[self.layout.room_cell(room, depth)] {
+                None => result = Some(depth),
+                Some(resident) if resident != pod => return None,
+                _ => {}
+            }
         }
-        let pod = pod.unwrap();
+        result
+    }
 
-        let destination_room = Self::dest_room(pod);
+    /// Whether the pod at `idx` is in its room with only its own kind below it.
+    fn settled(&self, idx: usize) -> bool {
+        let pod = match self.cells[idx] {
+            Some(pod) => pod,
+            None => return false,
+        };
+        let room = Layout::dest_room(pod);
+        if self.layout.room_num(idx) != Some(room) {
+            return false;
+        }
+        let bottom = self.layout.room_cell(room, self.layout.depth - 1);
+        (idx..=bottom).all(|i| self.cells[i] == Some(pod))
+    }
+
+    /// A lower bound on the energy still needed: every pod walks to the top of
+    /// its room as if nothing were in the way.
+    fn heuristic(&self) -> i64 {
+        let layout = &self.layout;
+        let mut result = 0;
+        for (idx, cell) in self.cells.iter().enumerate() {
+            let pod = match cell {
+                Some(pod) => *pod,
+                None => continue,
+            };
+            let target = layout.door(Layout::dest_room(pod));
+            let steps = match layout.room_num(idx) {
+                None => idx.abs_diff(target) + 1,
+                Some(_) if self.settled(idx) => 0,
+                // Out past the door, one step aside, and back in again
+                Some(room) if layout.door(room) == target => layout.depth_of(idx) + 4,
+                Some(room) => layout.depth_of(idx) + 1 + layout.door(room).abs_diff(target) + 1,
+            };
+            result += steps as i64 * Layout::step_cost(pod);
+        }
+        result
+    }
+
+    fn moves(&self, idx: usize) -> HashMap<World, i64> {
+        let mut result = HashMap::new();
+        let pod = match self.cells[idx] {
+            Some(pod) => pod,
+            None => return result,
+        };
+        let layout = &self.layout;
+        let destination_room = Layout::dest_room(pod);
 
         // If we are in the correct place, we do nothing
-        if Self::room_num(idx) == destination_room {
-            let base_idx = destination_room * 4 + 0xb;
-            if (base_idx..base_idx + 4)
-                .map(|i| self.0[i as usize])
-                .all(|v| v == Some(pod))
-            {
-                return result;
-            }
+        if self.settled(idx) {
+            return result;
         }
 
-        let step_cost = match pod {
-            'A' => 1,
-            'B' => 10,
-            'C' => 100,
-            'D' => 1000,
-            _ => panic!("Bad character"),
-        };
-        let can_stop_in_hall = !Self::is_hallway(idx);
+        let step_cost = Layout::step_cost(pod);
+        let can_stop_in_hall = !layout.is_hallway(idx);
 
-        let mut possibilities = vec![(idx, self.to_owned(), 0)];
+        let mut possibilities = vec![(idx, 0)];
         let mut visited = HashSet::new();
-        while !possibilities.is_empty() {
-            let (idx, current_world, cost) = possibilities.pop().unwrap();
-            if !visited.insert(current_world.to_owned()) {
+        while let Some((current, cost)) = possibilities.pop() {
+            if !visited.insert(current) {
                 continue;
             }
-            // println!("Considering at cost {}", cost);
-            // println!("{}", current_world);
-            for neighbor in &ADJACENCY2[idx] {
-                if current_world.0[*neighbor].is_some() {
+            for neighbor in &layout.adjacency[current] {
+                if self.cells[*neighbor].is_some() || visited.contains(neighbor) {
                     continue;
                 }
 
                 // If I'm in a hallway, I can step elsewhere in the hallway or into my room
-                if Self::is_hallway(idx) {
-                    if Self::is_hallway(*neighbor) {
-                        let dest = current_world.step_pod(idx, *neighbor);
-                        let valid_stop = can_stop_in_hall && !Self::is_doorway(*neighbor);
+                if layout.is_hallway(current) {
+                    if layout.is_hallway(*neighbor) {
+                        let valid_stop = can_stop_in_hall && !layout.is_doorway(*neighbor);
                         if valid_stop {
-                            insert_if_less2(&mut result, dest.to_owned(), cost + step_cost);
+                            result.insert(self.step_pod(idx, *neighbor), cost + step_cost);
                         }
-                        possibilities.push((*neighbor, dest, cost + step_cost));
-                    } else if Self::room_num(*neighbor) == destination_room {
-                        if current_world.0[neighbor + 3].is_none()
-                            && current_world.0[neighbor + 2].is_none()
-                            && current_world.0[neighbor + 1].is_none()
-                        {
-                            let dest = current_world.step_pod(idx, neighbor + 3);
-                            // insert_if_less2(&mut result, dest, cost+step_cost+step_cost);
-                            result.clear();
-                            result
-                                .insert(dest, cost + step_cost + step_cost + step_cost + step_cost);
-                            return result;
-                        } else if current_world.0[neighbor + 3] == Some(pod)
-                            && current_world.0[neighbor + 2].is_none()
-                            && current_world.0[neighbor + 1].is_none()
-                        {
-                            let dest = current_world.step_pod(idx, neighbor + 2);
-                            // insert_if_less2(&mut result, dest, cost+step_cost+step_cost);
-                            result.clear();
-                            result.insert(dest, cost + step_cost + step_cost + step_cost);
-                            return result;
-                        } else if current_world.0[neighbor + 3] == Some(pod)
-                            && current_world.0[neighbor + 2] == Some(pod)
-                            && current_world.0[neighbor + 1].is_none()
-                        {
-                            let dest = current_world.step_pod(idx, neighbor + 1);
-                            // insert_if_less2(&mut result, dest, cost+step_cost+step_cost);
-                            result.clear();
-                            result.insert(dest, cost + step_cost + step_cost);
-                            return result;
-                        } else if current_world.0[neighbor + 3] == Some(pod)
-                            && current_world.0[neighbor + 2] == Some(pod)
-                            && current_world.0[neighbor + 1] == Some(pod)
-                        {
-                            let dest = current_world.step_pod(idx, *neighbor);
-                            // insert_if_less2(&mut result, dest, cost+step_cost+step_cost);
+                        possibilities.push((*neighbor, cost + step_cost));
+                    } else if layout.room_num(*neighbor) == Some(destination_room) {
+                        // Going straight to the bottom of my room is always best
+                        if let Some(depth) = self.open_slot(destination_room, pod) {
+                            let dest = layout.room_cell(destination_room, depth);
+                            let cost = cost + step_cost * (depth as i64 + 1);
                             result.clear();
-                            result.insert(dest, cost + step_cost);
+                            result.insert(self.step_pod(idx, dest), cost);
                             return result;
                         }
                     }
                 } else {
-                    // I'm in a room that isn't my own. There are no valid stopping points from here
-                    let dest = current_world.step_pod(idx, *neighbor);
-                    possibilities.push((*neighbor, dest, cost + step_cost));
+                    // I'm in a room that I need to leave. There are no valid stopping points here
+                    possibilities.push((*neighbor, cost + step_cost));
                 }
             }
         }
@@ -532,169 +401,218 @@ impl World2 {
         result
     }
 
-    fn is_hallway(idx: usize) -> bool {
-        idx <= 0xa
-    }
-
-    fn is_doorway(idx: usize) -> bool {
-        ADJACENCY2[idx].len() > 2
+    fn win(&self) -> bool {
+        (self.layout.hallway_len()..self.layout.len())
+            .all(|idx| self.cells[idx].map(Layout::dest_room) == self.layout.room_num(idx))
     }
+}
 
-    fn room_num(idx: usize) -> u8 {
-        if idx < 0xb {
-            100
-        } else {
-            (idx as u8 - 0xb) / 4
-        }
-        // assert!(!Self::is_hallway(idx));
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Search {
+    Dijkstra,
+    AStar,
+}
 
-    fn dest_room(pod: char) -> u8 {
-        pod as u8 - b'A'
+/// Finds the cheapest way to sort the burrow.
+fn solve(world: &World, search: Search) -> Option<ShortestPath<World, i64>> {
+    let neighbors = |current: &World| {
+        (0..current.layout.len())
+            .flat_map(|idx| current.moves(idx))
+            .collect::<Vec<_>>()
+    };
+    match search {
+        Search::Dijkstra => dijkstra(world.to_owned(), neighbors, World::win),
+        Search::AStar => astar(world.to_owned(), neighbors, World::heuristic, World::win),
     }
+}
 
-    fn win(&self) -> bool {
-        for idx in 11..27 {
-            // println!("{:?} at {} dest is {} current is {}", self.0[idx], idx, Self::dest_room(self.0[idx].unwrap()), Self::room_num(idx));
-            if let Some(pod) = self.0[idx] {
-                if Self::dest_room(pod) != Self::room_num(idx) {
-                    return false;
-                }
-            } else {
-                return false;
-            }
-        }
-        true
-    }
+fn cheapest(world: &World, search: Search) -> Result<i64> {
+    let solution = solve(world, search).context("No solution")?;
+    println!("Expanded {} states with {:?}", solution.expanded, search);
+    Ok(solution.cost)
 }
 
-impl From<World> for World2 {
-    fn from(world1: World) -> Self {
-        let mut locs = [None; 27];
-        locs[11] = world1.0[11];
-        locs[12] = Some('D');
-        locs[13] = Some('D');
-        locs[14] = world1.0[12];
-
-        locs[15] = world1.0[13];
-        locs[16] = Some('C');
-        locs[17] = Some('B');
-        locs[18] = world1.0[14];
-
-        locs[19] = world1.0[15];
-        locs[20] = Some('B');
-        locs[21] = Some('A');
-        locs[22] = world1.0[16];
-
-        locs[23] = world1.0[17];
-        locs[24] = Some('A');
-        locs[25] = Some('C');
-        locs[26] = world1.0[18];
-        World2(locs)
-    }
+/// Renders each step of a solution with the energy spent so far.
+fn replay(path: &[(World, i64)]) -> String {
+    path.iter()
+        .enumerate()
+        .map(|(step, (world, cost))| format!("Step {} (energy {}):\n{}", step, cost, world))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
-lazy_static! {
-    static ref ADJACENCY2: Vec<Vec<usize>> = vec![
-        // Hallway
-        vec![1],
-        vec![0, 2],
-        vec![1, 11, 3],
-        vec![2, 4],
-        vec![3, 15, 5],
-        vec![4, 6],
-        vec![5, 19, 7],
-        vec![6, 8],
-        vec![7, 23, 9],
-        vec![8, 10],
-        vec![9],
-        // Room 0
-        vec![2, 12],
-        vec![11, 13],
-        vec![12, 14],
-        vec![13],
-        // Room 1
-        vec![4, 16],
-        vec![15, 17],
-        vec![16, 18],
-        vec![17],
-        // Room 2
-        vec![6, 20],
-        vec![19, 21],
-        vec![20, 22],
-        vec![21],
-        // Room 2
-        vec![8, 24],
-        vec![23, 25],
-        vec![24, 26],
-        vec![25],
-    ];
+
+/// Prints every step of the cheapest solution, to check a wrong answer by hand.
+fn print_replay(world: &World) -> Result<i64> {
+    let solution = solve(world, Search::AStar).context("No solution")?;
+    println!("{}", replay(&solution.path));
+    Ok(solution.cost)
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-struct HeapElement2 {
-    world: World2,
-    cost: i32,
+#[aoc_generator(day23, part1)]
+fn input_generator(input: &str) -> Result<World> {
+    input.parse()
 }
 
-impl HeapElement2 {
-    fn new(world: World2, cost: i32) -> Self {
-        HeapElement2 { world, cost }
+/// Unfolds depth 2 diagrams; deeper ones are taken as they are.
+#[aoc_generator(day23, part2)]
+fn input_generator2(input: &str) -> Result<World> {
+    let mut diagram: Diagram = input.parse()?;
+    if diagram.depth() == 2 {
+        diagram = diagram.unfold()?;
     }
+    Ok((&diagram).into())
 }
 
-impl From<(World2, i32)> for HeapElement2 {
-    fn from(tuple: (World2, i32)) -> Self {
-        HeapElement2::new(tuple.0, tuple.1)
-    }
+#[aoc(day23, part1)]
+fn part1(input: &World) -> Result<i64> {
+    cheapest(input, Search::Dijkstra)
 }
 
-impl PartialOrd for HeapElement2 {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
+#[aoc(day23, part2)]
+fn part2(input: &World) -> Result<i64> {
+    // println!("{}", input);
+    cheapest(input, Search::Dijkstra)
 }
 
-impl Ord for HeapElement2 {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.cost.cmp(&self.cost)
-    }
+#[aoc(day23, part1, astar)]
+fn part1_astar(input: &World) -> Result<i64> {
+    cheapest(input, Search::AStar)
 }
 
-#[aoc_generator(day23)]
-fn input_generator(_: &str) -> Result<Input> {
-    Ok([['D', 'C'], ['D', 'C'], ['A', 'B'], ['A', 'B']])
+#[aoc(day23, part2, astar)]
+fn part2_astar(input: &World) -> Result<i64> {
+    cheapest(input, Search::AStar)
 }
 
-#[aoc(day23, part1)]
-fn part1(input: &Input) -> Result<i32> {
-    let world = input.into();
-    Ok(solve(world))
+#[aoc(day23, part1, replay)]
+fn part1_replay(input: &World) -> Result<i64> {
+    print_replay(input)
 }
 
-#[aoc(day23, part2)]
-fn part2(input: &Input) -> Result<i32> {
-    let world: World = input.into();
-    let world: World2 = world.into();
-    // println!("{}", world);
-    Ok(solve2(world))
+#[aoc(day23, part2, replay)]
+fn part2_replay(input: &World) -> Result<i64> {
+    print_replay(input)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const SMOKE: Input = [['B', 'A'], ['C', 'D'], ['B', 'C'], ['D', 'A']];
+    const SMOKE: &str = "#############
+#...........#
+###B#C#B#D###
+  #A#D#C#A#
+  #########";
+
+    #[test]
+    fn parse() -> Result<()> {
+        let world = input_generator(SMOKE)?;
+        assert_eq!(SMOKE, world.to_string().trim_end());
+        let unfolded = input_generator2(SMOKE)?;
+        let expected = "#############
+#...........#
+###B#C#B#D###
+  #D#C#B#A#
+  #D#B#A#C#
+  #A#D#C#A#
+  #########";
+        assert_eq!(expected, unfolded.to_string().trim_end());
+        assert_eq!(unfolded, input_generator2(expected)?);
+        assert_eq!(unfolded, input_generator(expected)?);
+        assert!(input_generator(&SMOKE.replace('B', "E")).is_err());
+        assert!(input_generator(&SMOKE.replace("###B", "#.#B")).is_err());
+        assert!(input_generator(&SMOKE.replace("  #########", "  ####")).is_err());
+        assert!(input_generator(&SMOKE.replace("#A#D#C#A#", "#A#D#C#B#")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn path() -> Result<()> {
+        let world = input_generator(SMOKE)?;
+        let path = solve(&world, Search::Dijkstra).context("No solution")?.path;
+        println!("{}", replay(&path));
+        assert_eq!((world, 0), path[0]);
+        assert_eq!(12521, path[path.len() - 1].1);
+        assert!(path[path.len() - 1].0.win());
+        for step in path.windows(2) {
+            let (from, from_cost) = &step[0];
+            let (to, to_cost) = &step[1];
+            let moves = (0..from.layout.len()).flat_map(|idx| from.moves(idx));
+            assert!(moves
+                .into_iter()
+                .any(|(next, cost)| next == *to && cost == to_cost - from_cost));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn astar() -> Result<()> {
+        let world = input_generator2(SMOKE)?;
+        assert!(world.heuristic() <= 44169);
+        let dijkstra = solve(&world, Search::Dijkstra).context("No solution")?;
+        let astar = solve(&world, Search::AStar).context("No solution")?;
+        println!("Dijkstra: {} A*: {}", dijkstra.expanded, astar.expanded);
+        assert_eq!(
+            dijkstra.path.last().unwrap().1,
+            astar.path.last().unwrap().1
+        );
+        assert!(astar.expanded <= dijkstra.expanded);
+        assert_eq!(12521, part1_astar(&input_generator(SMOKE)?)?);
+        assert_eq!(44169, part2_astar(&world)?);
+        assert_eq!(12521, part1_replay(&input_generator(SMOKE)?)?);
+        assert_eq!(44169, part2_replay(&world)?);
+        Ok(())
+    }
+
+    #[test]
+    fn custom() -> Result<()> {
+        let world = input_generator(
+            "#########
+#.......#
+###B#A###
+  #####",
+        )?;
+        assert_eq!(46, part1(&world)?);
+        let world = input_generator(
+            "###########
+#.........#
+###B#A#C###
+  #A#B#C#
+  #A#B#C#
+  #######",
+        )?;
+        assert_eq!(46, part1(&world)?);
+
+        let solved = |rooms: usize| {
+            let row: String = (b'A'..)
+                .take(rooms)
+                .map(|pod| format!("#{}", pod as char))
+                .collect();
+            format!(
+                "{}\n#{}#\n###{}###\n  {}#\n  {}",
+                "#".repeat(rooms * 2 + 5),
+                ".".repeat(rooms * 2 + 3),
+                &row[1..],
+                row,
+                "#".repeat(rooms * 2 + 1)
+            )
+        };
+        assert_eq!(0, part1(&input_generator(&solved(17))?)?);
+        let error = input_generator(&solved(18)).unwrap_err();
+        assert!(error.to_string().contains("could overflow"), "{}", error);
+        Ok(())
+    }
 
     #[test]
     fn smoke1() -> Result<()> {
-        let mut world: World = (&SMOKE).into();
+        let mut world = input_generator(SMOKE)?;
         let mut cost = 0;
         println!("Smoke input\n{}", world);
         // Fully checked state
         for next in world.moves(15) {
             println!("Cost = {} (Winning={})", next.1 + cost, next.0.win());
             println!("{}", next.0);
-            if next.0 .0[3] == Some('B') {
+            if next.0.cells[3] == Some('B') {
                 world = next.0;
                 cost += next.1;
             }
@@ -703,7 +621,7 @@ mod tests {
         for next in world.moves(0xd) {
             println!("Cost = {} (Winning={})", next.1 + cost, next.0.win());
             println!("{}", next.0);
-            if next.0 .0[0xf] == Some('C') {
+            if next.0.cells[0xf] == Some('C') {
                 world = next.0;
                 cost += next.1;
             }
@@ -712,7 +630,7 @@ mod tests {
         for next in world.moves(0xe) {
             println!("Cost = {} (Winning={})", next.1 + cost, next.0.win());
             println!("{}", next.0);
-            if next.0 .0[5] == Some('D') {
+            if next.0.cells[5] == Some('D') {
                 world = next.0;
                 cost += next.1;
             }
@@ -721,7 +639,7 @@ mod tests {
         for next in world.moves(3) {
             println!("Cost = {} (Winning={})", next.1 + cost, next.0.win());
             println!("{}", next.0);
-            if next.0 .0[0xe] == Some('B') {
+            if next.0.cells[0xe] == Some('B') {
                 world = next.0;
                 cost += next.1;
             }
@@ -730,7 +648,7 @@ mod tests {
         for next in world.moves(0xb) {
             println!("Cost = {} (Winning={})", next.1 + cost, next.0.win());
             println!("{}", next.0);
-            if next.0 .0[0xd] == Some('B') {
+            if next.0.cells[0xd] == Some('B') {
                 world = next.0;
                 cost += next.1;
             }
@@ -739,7 +657,7 @@ mod tests {
         for next in world.moves(17) {
             println!("Cost = {} (Winning={})", next.1 + cost, next.0.win());
             println!("{}", next.0);
-            if next.0 .0[7] == Some('D') {
+            if next.0.cells[7] == Some('D') {
                 world = next.0;
                 cost += next.1;
             }
@@ -748,7 +666,7 @@ mod tests {
         for next in world.moves(18) {
             println!("Cost = {} (Winning={})", next.1 + cost, next.0.win());
             println!("{}", next.0);
-            if next.0 .0[9] == Some('A') {
+            if next.0.cells[9] == Some('A') {
                 world = next.0;
                 cost += next.1;
             }
@@ -757,7 +675,7 @@ mod tests {
         for next in world.moves(7) {
             println!("Cost = {} (Winning={})", next.1 + cost, next.0.win());
             println!("{}", next.0);
-            if next.0 .0[18] == Some('D') {
+            if next.0.cells[18] == Some('D') {
                 world = next.0;
                 cost += next.1;
             }
@@ -766,7 +684,7 @@ mod tests {
         for next in world.moves(5) {
             println!("Cost = {} (Winning={})", next.1 + cost, next.0.win());
             println!("{}", next.0);
-            if next.0 .0[17] == Some('D') {
+            if next.0.cells[17] == Some('D') {
                 world = next.0;
                 cost += next.1;
             }
@@ -775,25 +693,28 @@ mod tests {
         for next in world.moves(9) {
             println!("Cost = {} (Winning={})", next.1 + cost, next.0.win());
             println!("{}", next.0);
-            if next.0 .0[11] == Some('A') {
+            if next.0.cells[11] == Some('A') {
                 world = next.0;
                 cost += next.1;
             }
         }
         println!("Done at {} winning? {}", cost, world.win());
         println!("{}", world);
-        let winning_world: World = (&[['A', 'A'], ['B', 'B'], ['C', 'C'], ['D', 'D']]).into();
+        let winning_world = input_generator(
+            "#############
+#...........#
+###A#B#C#D###
+  #A#B#C#D#
+  #########",
+        )?;
         assert!(winning_world.win());
-        assert_eq!(12521, part1(&SMOKE)?);
+        assert_eq!(12521, part1(&input_generator(SMOKE)?)?);
         Ok(())
     }
 
     #[test]
     fn smoke2() -> Result<()> {
-        // let winning_world: World2 = (&[['A', 'A', 'A', 'A'], ['B', 'B', 'B', 'B'], ['C', 'C', 'C', 'C'], ['D', 'D', 'D', 'D']]).into();
-        // assert!(winning_world.win());
-        // let world: World = (&SMOKE).into();
-        // let mut world: World2 = world.into();
+        // let mut world = input_generator2(SMOKE)?;
         // let mut cost = 0;
         // println!("Smoke input\n{}", world);
         // // Fully checked state
@@ -996,7 +917,7 @@ mod tests {
         // }
         // println!("Done at {} winning? {}", cost, world.win());
         // println!("{}", world);
-        assert_eq!(44169, part2(&SMOKE)?);
+        assert_eq!(44169, part2(&input_generator2(SMOKE)?)?);
         Ok(())
     }
 }
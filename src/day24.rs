@@ -1,19 +1,316 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, str::FromStr};
 
-use anyhow::{ensure, Result};
+use anyhow::{bail, ensure, Context, Result};
+
+const Z: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Register(usize),
+    Literal(i64),
+}
+
+impl FromStr for Operand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(register) = parse_register(s) {
+            Ok(Operand::Register(register))
+        } else {
+            let value = s.parse().with_context(|| format!("Bad operand: {}", s))?;
+            Ok(Operand::Literal(value))
+        }
+    }
+}
+
+fn parse_register(s: &str) -> Result<usize> {
+    Ok(match s {
+        "w" => 0,
+        "x" => 1,
+        "y" => 2,
+        "z" => 3,
+        _ => bail!("Unknown register: {}", s),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Inp(usize),
+    Add(usize, Operand),
+    Mul(usize, Operand),
+    Div(usize, Operand),
+    Mod(usize, Operand),
+    Eql(usize, Operand),
+}
+
+impl FromStr for Instruction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let op = parts.next().context("Empty instruction")?;
+        let a = parse_register(parts.next().context("Missing register")?)?;
+        let result = if op == "inp" {
+            Instruction::Inp(a)
+        } else {
+            let b = parts.next().context("Missing operand")?.parse()?;
+            match op {
+                "add" => Instruction::Add(a, b),
+                "mul" => Instruction::Mul(a, b),
+                "div" => Instruction::Div(a, b),
+                "mod" => Instruction::Mod(a, b),
+                "eql" => Instruction::Eql(a, b),
+                _ => bail!("Unknown instruction: {}", op),
+            }
+        };
+        ensure!(parts.next().is_none(), "Trailing tokens: {}", s);
+        Ok(result)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct Alu {
+    registers: [i64; 4],
+}
+
+impl Alu {
+    fn value(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Register(r) => self.registers[r],
+            Operand::Literal(v) => v,
+        }
+    }
+
+    fn execute(
+        &mut self,
+        instruction: &Instruction,
+        input: &mut impl Iterator<Item = i64>,
+    ) -> Result<()> {
+        match *instruction {
+            Instruction::Inp(a) => self.registers[a] = input.next().context("Out of input")?,
+            Instruction::Add(a, b) => self.registers[a] += self.value(b),
+            Instruction::Mul(a, b) => self.registers[a] *= self.value(b),
+            Instruction::Div(a, b) => {
+                let b = self.value(b);
+                ensure!(b != 0, "Division by zero");
+                self.registers[a] /= b;
+            }
+            Instruction::Mod(a, b) => {
+                let b = self.value(b);
+                ensure!(
+                    self.registers[a] >= 0 && b > 0,
+                    "Invalid mod: {} % {}",
+                    self.registers[a],
+                    b
+                );
+                self.registers[a] %= b;
+            }
+            Instruction::Eql(a, b) => {
+                self.registers[a] = (self.registers[a] == self.value(b)) as i64
+            }
+        }
+        Ok(())
+    }
+
+    fn run(
+        &mut self,
+        instructions: &[Instruction],
+        input: impl IntoIterator<Item = i64>,
+    ) -> Result<()> {
+        let mut input = input.into_iter();
+        for instruction in instructions {
+            self.execute(instruction, &mut input)?;
+        }
+        Ok(())
+    }
+}
+
+/// A MONAD program split into blocks, each of which starts by reading one digit.
+#[derive(Debug, Clone)]
+struct Program {
+    blocks: Vec<Vec<Instruction>>,
+}
+
+#[aoc_generator(day24)]
+fn input_generator(input: &str) -> Result<Program> {
+    let mut blocks: Vec<Vec<Instruction>> = vec![];
+    for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let instruction: Instruction = line.parse()?;
+        if let Instruction::Inp(_) = instruction {
+            blocks.push(vec![]);
+        }
+        blocks
+            .last_mut()
+            .context("Program must start with inp")?
+            .push(instruction);
+    }
+    ensure!(!blocks.is_empty(), "Empty program");
+    Ok(Program { blocks })
+}
+
+/// Parameters of one standard MONAD block: `div z {div}`, `add x {check}`, `add y {offset}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BlockParams {
+    div: i64,
+    check: i64,
+    offset: i64,
+}
+
+fn monad_block(params: &BlockParams) -> Result<Vec<Instruction>> {
+    format!(
+        "inp w
+mul x 0
+add x z
+mod x 26
+div z {}
+add x {}
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y {}
+mul y x
+add z y",
+        params.div, params.check, params.offset
+    )
+    .lines()
+    .map(str::parse)
+    .collect()
+}
+
+fn block_params(block: &[Instruction]) -> Option<BlockParams> {
+    let literal = |idx: usize| match block.get(idx)? {
+        Instruction::Div(_, Operand::Literal(v)) | Instruction::Add(_, Operand::Literal(v)) => {
+            Some(*v)
+        }
+        _ => None,
+    };
+    let params = BlockParams {
+        div: literal(4)?,
+        check: literal(5)?,
+        offset: literal(15)?,
+    };
+    if monad_block(&params).ok()? == block {
+        Some(params)
+    } else {
+        None
+    }
+}
+
+/// A pairwise digit constraint `serial[pop] == serial[push] + delta`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Constraint {
+    push: usize,
+    pop: usize,
+    delta: i64,
+}
+
+/// Treats `z` as a base-26 stack: blocks with `div z 1` always push `w + offset`,
+/// blocks with `div z 26` pop and must match `w == popped + check` to avoid pushing again.
+/// Returns `None` when the program doesn't follow this structure.
+fn constraints(program: &Program) -> Option<Vec<Constraint>> {
+    let mut stack = vec![];
+    let mut result = vec![];
+    for (idx, block) in program.blocks.iter().enumerate() {
+        let params = block_params(block)?;
+        match params.div {
+            // A check above 9 can never match a digit, so the push is unconditional.
+            1 if params.check > 9 => stack.push((idx, params.offset)),
+            26 => {
+                let (push, offset) = stack.pop()?;
+                result.push(Constraint {
+                    push,
+                    pop: idx,
+                    delta: offset + params.check,
+                });
+            }
+            _ => return None,
+        }
+    }
+    if stack.is_empty() {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+fn solve_constraints(constraints: &[Constraint], digits: usize, largest: bool) -> Result<Vec<u8>> {
+    let mut serial = vec![0; digits];
+    for c in constraints {
+        ensure!(c.delta.abs() < 9, "Unsatisfiable constraint: {:?}", c);
+        let push = if largest {
+            9.min(9 - c.delta)
+        } else {
+            1.max(1 - c.delta)
+        };
+        serial[c.push] = push as u8;
+        serial[c.pop] = (push + c.delta) as u8;
+    }
+    Ok(serial)
+}
+
+fn verify(program: &Program, serial: &[u8]) -> Result<bool> {
+    let mut alu = Alu::default();
+    for (block, digit) in program.blocks.iter().zip(serial) {
+        alu.run(block, [*digit as i64])?;
+    }
+    Ok(alu.registers[Z] == 0)
+}
+
+fn to_number(serial: &[u8]) -> i64 {
+    let mut result = 0;
+    for digit in serial {
+        result *= 10;
+        result += *digit as i64;
+    }
+    result
+}
+
+/// Solves symbolically when possible, falling back to the exhaustive search otherwise.
+fn solve(program: &Program, largest: bool) -> Result<i64> {
+    match constraints(program) {
+        Some(constraints) => {
+            let serial = solve_constraints(&constraints, program.blocks.len(), largest)?;
+            if verify(program, &serial)? {
+                return Ok(to_number(&serial));
+            }
+            println!(
+                "Symbolic solution {} failed verification, falling back to search",
+                to_number(&serial)
+            );
+        }
+        None => println!("Program is not a standard MONAD, falling back to search"),
+    }
+    run(program, largest)
+}
 
 #[aoc(day24, part1)]
-fn part1(_: &str) -> Result<i64> {
-    run(true)
+fn part1(input: &Program) -> Result<i64> {
+    solve(input, true)
 }
 
 #[aoc(day24, part2)]
-fn part2(_: &str) -> Result<i64> {
-    run(false)
+fn part2(input: &Program) -> Result<i64> {
+    solve(input, false)
 }
 
-pub fn run(reverse: bool) -> Result<i64> {
-    let mut serial = [0; 14];
+#[aoc(day24, part1, search)]
+fn part1_search(input: &Program) -> Result<i64> {
+    run(input, true)
+}
+
+#[aoc(day24, part2, search)]
+fn part2_search(input: &Program) -> Result<i64> {
+    run(input, false)
+}
+
+fn run(program: &Program, reverse: bool) -> Result<i64> {
+    let mut serial = vec![0; program.blocks.len()];
     let registers = [0; 4];
     let mut seen = HashSet::new();
     let digits = if reverse {
@@ -21,239 +318,218 @@ pub fn run(reverse: bool) -> Result<i64> {
     } else {
         [1, 2, 3, 4, 5, 6, 7, 8, 9]
     };
-    ensure!(driver(&digits, 0, &mut serial, registers, &mut seen));
+    ensure!(
+        driver(program, &digits, 0, &mut serial, registers, &mut seen)?,
+        "No valid model number"
+    );
 
-    let mut result = 0;
-    for digit in serial {
-        result *= 10;
-        result += digit as i64;
-    }
-    Ok(result)
+    Ok(to_number(&serial))
 }
 
-pub fn driver(
+fn driver(
+    program: &Program,
     digits: &[u8],
-    step: u8,
-    serial: &mut [u8; 14],
+    step: usize,
+    serial: &mut [u8],
     registers: [i64; 4],
-    seen: &mut HashSet<(u8, [i64; 4])>,
-) -> bool {
+    seen: &mut HashSet<(usize, [i64; 4])>,
+) -> Result<bool> {
     if !seen.insert((step, registers)) {
-        return false;
+        return Ok(false);
     }
 
+    let last = program.blocks.len() - 1;
     for digit in digits.iter() {
-        let mut registers = registers;
+        let mut alu = Alu { registers };
         if step == 1 {
-            println!("Candidate: {:?} -> {:?} (Saved states: {})", serial, registers, seen.len());
+            println!(
+                "Candidate: {:?} -> {:?} (Saved states: {})",
+                serial,
+                registers,
+                seen.len()
+            );
         }
-        serial[step as usize] = *digit;
-        checksum(step, serial, &mut registers);
-        if step == 13 && registers[3] == 0 {
-            return true;
+        serial[step] = *digit;
+        alu.run(&program.blocks[step], [*digit as i64])?;
+        if step == last && alu.registers[Z] == 0 {
+            return Ok(true);
         }
-        if step < 13 && driver(digits, step + 1, serial, registers, seen) {
-            return true;
+        if step < last && driver(program, digits, step + 1, serial, alu.registers, seen)? {
+            return Ok(true);
         }
     }
-    false
+    Ok(false)
 }
 
-#[allow(clippy::assign_op_pattern)]
-pub fn checksum(step: u8, serial: &[u8], registers: &mut [i64; 4]) {
-    match step {
-        0 => {
-            registers[0] = serial[0] as i64;
-            registers[2] = registers[0] + 12;
-            registers[3] = registers[2];
-            // Shifted
-            registers[2] = 26;
-            registers[3] = registers[3] * registers[2];
-        }
-        1 => {
-            registers[0] = serial[1] as i64;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            registers[2] = registers[0] + 7;
-            registers[3] = registers[3] + registers[2];
+    const SMOKE: &str = "inp w
+mul x 0
+add x z
+mod x 26
+div z 1
+add x 12
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y 3
+mul y x
+add z y
+inp w
+mul x 0
+add x z
+mod x 26
+div z 1
+add x 11
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y 5
+mul y x
+add z y
+inp w
+mul x 0
+add x z
+mod x 26
+div z 26
+add x -7
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y 2
+mul y x
+add z y
+inp w
+mul x 0
+add x z
+mod x 26
+div z 26
+add x -2
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y 8
+mul y x
+add z y";
 
-            // Shifted
-            registers[2] = 26;
-            registers[3] = registers[3] * registers[2];
-        }
-        2 => {
-            registers[0] = serial[2] as i64;
+    #[test]
+    fn alu() -> Result<()> {
+        let negate = input_generator("inp x\nmul x -1")?;
+        let mut alu = Alu::default();
+        alu.run(&negate.blocks[0], [7])?;
+        assert_eq!(-7, alu.registers[1]);
 
-            registers[2] = registers[0] + 1;
-            registers[3] = registers[3] + registers[2];
+        let binary = input_generator(
+            "inp w
+add z w
+mod z 2
+div w 2
+add y w
+mod y 2
+div w 2
+add x w
+mod x 2
+div w 2
+mod w 2",
+        )?;
+        let mut alu = Alu::default();
+        alu.run(&binary.blocks[0], [13])?;
+        assert_eq!([1, 1, 0, 1], alu.registers);
 
-            // shifted
-            registers[2] = 26;
-            registers[3] = registers[3] * registers[2];
-        }
-        3 => {
-            registers[0] = serial[3] as i64;
-
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 2;
-            registers[3] = registers[3] + registers[2];
-
-            // shifted
-            registers[1] = registers[3];
-            registers[1] = registers[1] % 26;
-            registers[3] = registers[3] / 26;
-            registers[1] = registers[1] + -5;
-            registers[2] = 25;
-        }
-        4 => {
-            registers[0] = serial[4] as i64;
-            registers[1] = if registers[1] == registers[0] { 0 } else { 1 };
-
-            registers[2] = registers[2] * registers[1];
-            registers[2] = registers[2] + 1;
-            registers[3] = registers[3] * registers[2];
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 4;
-            registers[2] = registers[2] * registers[1];
-            registers[3] = registers[3] + registers[2];
-
-            // shifted
-            registers[2] = 26;
-            registers[3] = registers[3] * registers[2];
-        }
-        5 => {
-            registers[0] = serial[5] as i64;
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 15;
-            registers[3] = registers[3] + registers[2];
-
-            // shifted
-            registers[2] = 26;
-            registers[3] = registers[3] * registers[2];
-        }
-        6 => {
-            registers[0] = serial[6] as i64;
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 11;
-            registers[3] = registers[3] + registers[2];
-
-            // shifted
-            registers[1] = registers[3];
-            registers[1] = registers[1] % 26;
-            registers[3] = registers[3] / 26;
-            registers[1] = registers[1] + -13;
-            registers[2] = 25;
-        }
-        7 => {
-            registers[0] = serial[7] as i64;
-            registers[1] = if registers[1] == registers[0] { 0 } else { 1 };
-            registers[2] = registers[2] * registers[1];
-            registers[2] = registers[2] + 1;
-            registers[3] = registers[3] * registers[2];
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 5;
-            registers[2] = registers[2] * registers[1];
-            registers[3] = registers[3] + registers[2];
-
-            // shifted
-            registers[1] = registers[3];
-            registers[1] = registers[1] % 26;
-            registers[3] = registers[3] / 26;
-            registers[1] = registers[1] + -16;
-            registers[2] = 25;
-        }
-        8 => {
-            registers[0] = serial[8] as i64;
-            registers[1] = if registers[1] == registers[0] { 0 } else { 1 };
-            registers[2] = registers[2] * registers[1];
-            registers[2] = registers[2] + 1;
-            registers[3] = registers[3] * registers[2];
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 3;
-            registers[2] = registers[2] * registers[1];
-            registers[3] = registers[3] + registers[2];
-
-            // shifted
-            registers[1] = registers[3];
-            registers[1] = registers[1] % 26;
-            registers[3] = registers[3] / 26;
-            registers[1] = registers[1] + -8;
-            registers[2] = 25;
-        }
-        9 => {
-            registers[0] = serial[9] as i64;
-            registers[1] = if registers[1] == registers[0] { 0 } else { 1 };
-            registers[2] = registers[2] * registers[1];
-            registers[2] = registers[2] + 1;
-            registers[3] = registers[3] * registers[2];
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 9;
-            registers[2] = registers[2] * registers[1];
-            registers[3] = registers[3] + registers[2];
-
-            // shifted
-            registers[2] = 26;
-            registers[3] = registers[3] * registers[2];
-        }
-        10 => {
-            registers[0] = serial[10] as i64;
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 2;
-            registers[3] = registers[3] + registers[2];
-
-            // shifted
-            registers[1] = registers[3];
-            registers[1] = registers[1] % 26;
-            registers[3] = registers[3] / 26;
-            registers[1] = registers[1] + -8;
-            registers[2] = 25;
-        }
-        11 => {
-            registers[0] = serial[11] as i64;
-            registers[1] = if registers[1] == registers[0] { 0 } else { 1 };
-            registers[2] = registers[2] * registers[1];
-            registers[2] = registers[2] + 1;
-            registers[3] = registers[3] * registers[2];
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 3;
-            registers[2] = registers[2] * registers[1];
-            registers[3] = registers[3] + registers[2];
-
-            // shifted
-            registers[1] = registers[3];
-            registers[1] = registers[1] % 26;
-            registers[3] = registers[3] / 26;
-            registers[2] = 25;
-        }
-        12 => {
-            registers[0] = serial[12] as i64;
-            registers[1] = if registers[1] == registers[0] { 0 } else { 1 };
-            registers[2] = registers[2] * registers[1];
-            registers[2] = registers[2] + 1;
-            registers[3] = registers[3] * registers[2];
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 3;
-            registers[2] = registers[2] * registers[1];
-            registers[3] = registers[3] + registers[2];
-
-            // shifted
-            registers[1] = registers[3];
-            registers[1] = registers[1] % 26;
-            registers[3] = registers[3] / 26;
-            registers[1] = registers[1] + -4;
-            registers[2] = 25;
-        }
-        13 => {
-            registers[0] = serial[13] as i64;
-
-            registers[1] = if registers[1] == registers[0] { 0 } else { 1 };
-            registers[2] = registers[2] * registers[1];
-            registers[2] = registers[2] + 1;
-            registers[3] = registers[3] * registers[2];
-            registers[2] = registers[0];
-            registers[2] = registers[2] + 11;
-            registers[2] = registers[2] * registers[1];
-            registers[3] = registers[3] + registers[2];
-        }
-        _ => {}
+        let mut alu = Alu::default();
+        assert!(alu
+            .run(&input_generator("inp w\ndiv w 0")?.blocks[0], [1])
+            .is_err());
+        assert!("inp a".parse::<Instruction>().is_err());
+        assert!("add x".parse::<Instruction>().is_err());
+        assert!(input_generator("add x 1\ninp w").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn smoke1() -> Result<()> {
+        let input = input_generator(SMOKE)?;
+        assert_eq!(4, input.blocks.len());
+        assert_eq!(8979, part1(&input)?);
+        Ok(())
+    }
+
+    #[test]
+    fn smoke2() -> Result<()> {
+        let input = input_generator(SMOKE)?;
+        assert_eq!(1312, part2(&input)?);
+        Ok(())
+    }
+
+    #[test]
+    fn smoke_search() -> Result<()> {
+        let input = input_generator(SMOKE)?;
+        assert_eq!(8979, part1_search(&input)?);
+        assert_eq!(1312, part2_search(&input)?);
+        Ok(())
+    }
+
+    #[test]
+    fn symbolic() -> Result<()> {
+        let params = [
+            (1, 12, 12),
+            (1, 11, 7),
+            (1, 13, 1),
+            (1, 12, 2),
+            (26, -5, 4),
+            (1, 10, 15),
+            (1, 14, 11),
+            (26, -13, 5),
+            (26, -16, 3),
+            (26, -8, 9),
+            (1, 15, 2),
+            (26, -8, 3),
+            (26, 0, 3),
+            (26, -4, 11),
+        ];
+        let blocks = params
+            .iter()
+            .map(|&(div, check, offset)| monad_block(&BlockParams { div, check, offset }))
+            .collect::<Result<_>>()?;
+        let input = Program { blocks };
+        assert_eq!(7, constraints(&input).context("Not a MONAD")?.len());
+        assert_eq!(12996997829399, part1(&input)?);
+        assert_eq!(11841231117189, part2(&input)?);
+        Ok(())
+    }
+
+    #[test]
+    fn fallback() -> Result<()> {
+        let input = input_generator(&SMOKE.replacen("add y 25", "add y 24", 1))?;
+        assert!(constraints(&input).is_none());
+        assert_eq!(part1_search(&input)?, part1(&input)?);
+        assert_eq!(part2_search(&input)?, part2(&input)?);
+        Ok(())
     }
 }
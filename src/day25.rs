@@ -1,55 +1,38 @@
 use anyhow::Result;
 
-use crate::MatrixTranspose;
+use crate::Grid;
 
-type Input = Vec<Vec<char>>;
+type Input = Grid<char>;
 
 #[aoc_generator(day25)]
 fn input_generator(input: &str) -> Result<Input> {
-    let grid: Vec<Vec<char>> = input.lines().map(|l| l.chars().collect::<Vec<char>>()).collect();
-    let grid = grid.transpose();
-    Ok(grid)
+    Ok(Grid::parse_chars(input)?.with_wrapping(true))
 }
 
 #[allow(dead_code)]
 fn print_floor(floor: &Input) {
-    let max_x = floor.len();
-    let max_y = floor[0].len();
-    for y in 0..max_y {
-        for x in 0..max_x {
-            print!("{}", floor[x][y]);
-        }
-        println!();
-    }
-    println!();
-
+    println!("{}", floor);
 }
 
 fn step(floor: &Input, herd: char) -> (Input, usize) {
-    let (x_off, y_off) = match herd {
+    let offset = match herd {
         '>' => (1, 0),
         'v' => (0, 1),
         _ => panic!("Invalid herd")
     };
-    let max_x = floor.len();
-    let max_y = floor[0].len();
-    let mut next_floor = vec![vec!['.'; max_y]; max_x];
+    let mut next_floor = floor.map(|_| '.');
     let mut move_count = 0;
-    for (x, row) in floor.iter().enumerate() {
-        for (y, spot) in row.iter().enumerate() {
-            if *spot == herd {
-                let (dest_x, dest_y) = (x + x_off, y + y_off);
-                let dest_x = dest_x % max_x;
-                let dest_y = dest_y % max_y;
-                if floor[dest_x][dest_y] == '.' {
-                    move_count += 1;
-                    next_floor[dest_x][dest_y] = *spot;
-                } else {
-                    next_floor[x][y] = *spot;
-                }
-            } else if *spot != '.' {
-                next_floor[x][y] = *spot;
+    for (coord, spot) in floor.iter() {
+        if *spot == herd {
+            let dest = floor.offset(coord, offset).unwrap();
+            if floor[dest] == '.' {
+                move_count += 1;
+                next_floor[dest] = *spot;
+            } else {
+                next_floor[coord] = *spot;
             }
+        } else if *spot != '.' {
+            next_floor[coord] = *spot;
         }
     }
     (next_floor, move_count)
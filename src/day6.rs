@@ -1,6 +1,8 @@
-use std::{collections::VecDeque, str::FromStr};
+use std::{collections::HashMap, str::FromStr};
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
+
+use crate::{Count, ReplacementEngine};
 
 #[derive(Clone, Copy)]
 struct Fish {
@@ -45,36 +47,30 @@ fn part1(input: &[u8]) -> Result<usize> {
         //     print!("{},", f.phase);
         // }
         // println!();
-        let mut new_fish = input.iter_mut().map(|f| f.spawn()).flatten().collect();
+        let mut new_fish = input.iter_mut().filter_map(|f| f.spawn()).collect();
         input.append(&mut new_fish);
     }
     Ok(input.len())
 }
 
-fn fast_count(input: &[u8], days: usize) -> Result<u64> {
-    let mut counts = [0u64; 7];
-    let mut next_counts = VecDeque::new();
-    next_counts.extend(std::iter::repeat(0).take(9));
-
+/// Counts fish by days left until they spawn, so each day is a fixed
+/// replacement of timers and any number of days can be skipped at once.
+fn fast_count<C: Count>(input: &[u8], days: usize) -> Result<C> {
+    let mut counts = HashMap::new();
     for f in input {
-        counts[*f as usize] += 1;
+        ensure!(*f <= 8, "Bad timer {}", f);
+        let count = counts.entry(*f).or_insert_with(C::zero);
+        *count = count.checked_add(&C::one()).context("Count overflow")?;
     }
-    let mut ptr = 0;
-    for _day in 0..days {
-        if let Some(spawn) = next_counts.pop_front() {
-            counts[ptr] += spawn;
-        }
-        // println!(
-        //     "Day {}: Total {} ({:?}, {:?})",
-        //     _day,
-        //     counts.iter().sum::<u64>() + next_counts.iter().sum::<u64>(),
-        //     counts,
-        //     next_counts
-        // );
-        next_counts.push_back(counts[ptr]);
-        ptr = (ptr + 1) % 7;
+    let mut rules = HashMap::new();
+    rules.insert(0, vec![(6, C::one()), (8, C::one())]);
+    for timer in 1..=8 {
+        rules.insert(timer, vec![(timer - 1, C::one())]);
     }
-    Ok(counts.iter().sum::<u64>() + next_counts.iter().sum::<u64>())
+
+    let mut engine = ReplacementEngine::new(counts, rules);
+    engine.advance(days as u64)?;
+    engine.total()
 }
 
 #[aoc(day6, part1, fast)]
@@ -89,7 +85,13 @@ fn part2(input: &[u8]) -> Result<u64> {
 
 #[cfg(test)]
 mod tests {
+    use num_bigint::BigUint;
+
     use super::*;
+    use crate::{
+        differential::{check_agreement, solver, without_each, Rng},
+        Modular,
+    };
 
     const SMOKE: &str = "3,4,3,1,2";
 
@@ -111,6 +113,51 @@ mod tests {
     fn smoke2() -> Result<()> {
         let input = input_generator(SMOKE)?;
         assert_eq!(26984457539u64, part2(&input)?);
+        assert_eq!(26u64, fast_count(&input, 18)?);
         Ok(())
     }
+
+    #[test]
+    fn long_run() -> Result<()> {
+        let input = input_generator(SMOKE)?;
+        assert!(fast_count::<u64>(&input, 600).is_err());
+        let exact: u128 = fast_count(&input, 600)?;
+        let big: BigUint = fast_count(&input, 600)?;
+        assert_eq!(exact.to_string(), big.to_string());
+        let modular: Modular<1_000_000_007> = fast_count(&input, 600)?;
+        assert_eq!((exact % 1_000_000_007) as u64, modular.value());
+        let huge: BigUint = fast_count(&input, 5000)?;
+        assert!(huge.bits() > 500);
+        Ok(())
+    }
+
+    #[test]
+    fn variants_agree() {
+        check_agreement(
+            30,
+            |rng: &mut Rng| {
+                (0..rng.range(1, 12))
+                    .map(|_| rng.range(0, 8))
+                    .collect::<Vec<_>>()
+            },
+            |fish| {
+                without_each(fish)
+                    .into_iter()
+                    .filter(|f| !f.is_empty())
+                    .collect()
+            },
+            |fish| {
+                fish.iter()
+                    .map(|f| f.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            },
+            &[
+                solver("part1", &|s| Ok(part1(&input_generator(s)?)?.to_string())),
+                solver("part1_array", &|s| {
+                    Ok(part1_array(&input_generator(s)?)?.to_string())
+                }),
+            ],
+        );
+    }
 }
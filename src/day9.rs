@@ -1,43 +1,25 @@
 use std::collections::HashSet;
 
 use anyhow::Result;
-use itertools::iproduct;
+
+use crate::Grid;
 
 #[aoc_generator(day9)]
-fn input_generator(input: &str) -> Result<Vec<Vec<u8>>> {
-    let result = input
-        .lines()
-        .map(|l| l.chars().map(|c| c as u8 - b'0').collect())
-        .collect();
-    Ok(result)
+fn input_generator(input: &str) -> Result<Grid<u8>> {
+    Grid::parse_digits(input)
 }
 
-fn find_lows(input: &[Vec<u8>]) -> Vec<(usize, usize)> {
+fn find_lows(input: &Grid<u8>) -> Vec<(usize, usize)> {
     let mut result = vec![];
-    let height = input.len() as i32;
-    let width = input[0].len() as i32;
-    for (y, x) in iproduct!(0..height, 0..width) {
-        let elevation = input[y as usize][x as usize];
-        let mut low_point = true;
-        // println!("({}, {})={}", x, y, elevation);
-        for (x_off, y_off) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
-            let other = input
-                .get((y + y_off) as usize)
-                .map(|row| row.get((x + x_off) as usize))
-                .flatten();
-            low_point &= other.map(|o| *o > elevation).unwrap_or(true);
-            // println!("\t({},{})={:?}, {}", x + x_off, y + y_off, other, low_point);
-        }
+    for (coord, elevation) in input.iter() {
+        // println!("({:?})={}", coord, elevation);
+        let low_point = input
+            .neighbors4(coord)
+            .all(|other| input[other] > *elevation);
 
-        // if x == 0 {
-        //     println!();
-        // }
         if low_point {
-            result.push((x as usize, y as usize));
-            // println!("({}, {})={} -> {}", x, y, elevation, low_point);
-            // print!("{}", elevation.to_string().blue());
-            // } else {
-            //     print!("{}", elevation.to_string().red());
+            result.push(coord);
+            // println!("{:?}={} -> {}", coord, elevation, low_point);
         }
     }
     // println!("{:?}", result);
@@ -45,15 +27,15 @@ fn find_lows(input: &[Vec<u8>]) -> Vec<(usize, usize)> {
 }
 
 #[aoc(day9, part1)]
-fn part1(input: &[Vec<u8>]) -> Result<u64> {
+fn part1(input: &Grid<u8>) -> Result<u64> {
     Ok(find_lows(input)
         .iter()
-        .map(|(x, y)| input[*y][*x] as u64 + 1)
+        .map(|coord| input[*coord] as u64 + 1)
         .sum())
 }
 
 #[aoc(day9, part2)]
-fn part2(input: &[Vec<u8>]) -> Result<u64> {
+fn part2(input: &Grid<u8>) -> Result<u64> {
     let low_points = find_lows(input);
     let mut sizes = vec![];
 
@@ -63,20 +45,9 @@ fn part2(input: &[Vec<u8>]) -> Result<u64> {
 
         while let Some(current) = queue.pop() {
             visited.insert(current);
-            // let elevation = input[current.1][current.0];
-            for (x_off, y_off) in [(-1i32, 0i32), (1, 0), (0, -1), (0, 1)] {
-                let new_x = (current.0 as i32 + x_off) as usize;
-                let new_y = (current.1 as i32 + y_off) as usize;
-                if visited.contains(&(new_x, new_y)) {
-                    continue;
-                }
-                let new_elevation = input
-                    .get(new_y)
-                    .map(|row| row.get(new_x))
-                    .flatten()
-                    .unwrap_or(&9);
-                if *new_elevation != 9 {
-                    queue.push((new_x, new_y));
+            for next in input.neighbors4(current) {
+                if !visited.contains(&next) && input[next] != 9 {
+                    queue.push(next);
                 }
             }
         }
@@ -1,10 +1,15 @@
 use std::{
     borrow::Borrow,
-    collections::HashMap,
-    fmt::Display,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    env,
+    fmt::{Debug, Display},
     fs::File,
     hash::Hash,
-    io::{BufRead, BufReader, Lines},
+    io::{stdin, BufRead, BufReader, Lines},
+    ops::{Add, Index, IndexMut},
+    path::PathBuf,
+    str::FromStr,
 };
 
 mod day10;
@@ -32,17 +37,103 @@ mod day7;
 mod day8;
 mod day9;
 
-use anyhow::{Context, Result};
+pub use day16::{compile as compile_bits, LengthType};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use aoc_runner::{ArcStr, Runner};
+use num_bigint::BigUint;
 #[macro_use]
 extern crate aoc_runner_derive;
 
-const FILE_BASE: &str = if cfg!(windows) { r"res\" } else { "res/" };
+const DEFAULT_INPUT_DIR: &str = "res";
+/// Overrides the directory puzzle inputs are read from.
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+/// Selects a per-user subdirectory of the input directory.
+pub const INPUT_USER_VAR: &str = "AOC_USER";
+/// The file name that reads from stdin instead.
+pub const STDIN: &str = "-";
+
+type InputLines = Lines<Box<dyn BufRead>>;
+
+/// Where puzzle inputs live: `<dir>/<file>`, or `<dir>/<user>/<file>` when
+/// several people's inputs are kept side by side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputLocation {
+    dir: PathBuf,
+    user: Option<String>,
+}
+
+impl InputLocation {
+    pub fn new(dir: impl Into<PathBuf>, user: Option<&str>) -> Self {
+        Self {
+            dir: dir.into(),
+            user: user.map(str::to_owned),
+        }
+    }
+
+    /// `res/` unless overridden by `AOC_INPUT_DIR` and `AOC_USER`.
+    pub fn from_env() -> Self {
+        let dir = env::var_os(INPUT_DIR_VAR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_INPUT_DIR));
+        let user = env::var(INPUT_USER_VAR).ok().filter(|u| !u.is_empty());
+        Self { dir, user }
+    }
+
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    pub fn with_user(mut self, user: &str) -> Self {
+        self.user = Some(user.to_owned());
+        self
+    }
+
+    pub fn path(&self, file_name: &str) -> PathBuf {
+        let mut result = self.dir.clone();
+        if let Some(user) = &self.user {
+            result.push(user);
+        }
+        result.push(file_name);
+        result
+    }
+
+    pub fn open(&self, file_name: &str) -> Result<InputLines> {
+        let reader: Box<dyn BufRead> = if file_name == STDIN {
+            Box::new(BufReader::new(stdin()))
+        } else {
+            let path = self.path(file_name);
+            let input =
+                File::open(&path).with_context(|| format!("Could not open {}", path.display()))?;
+            Box::new(BufReader::new(input))
+        };
+        Ok(reader.lines())
+    }
+
+    /// The whole of `file_name` with its lines joined by `\n`.
+    pub fn read(&self, file_name: &str) -> Result<String> {
+        let lines: Vec<String> = self
+            .open(file_name)?
+            .collect::<Result<_, _>>()
+            .with_context(|| format!("Could not read {}", self.path(file_name).display()))?;
+        Ok(lines.join("\n"))
+    }
 
-pub fn read_file(file_name: &str) -> Result<Lines<BufReader<File>>> {
-    let input = File::open(FILE_BASE.to_owned() + file_name).context("Could not open file")?;
-    let reader = BufReader::new(input);
+    /// The whole input for `day`, with a hint about the override variables
+    /// when it is missing.
+    pub fn read_day(&self, day: u8) -> Result<String> {
+        self.read(&format!("day{}.txt", day)).with_context(|| {
+            format!(
+                "No input for day {} (set {} or {} to read from elsewhere)",
+                day, INPUT_DIR_VAR, INPUT_USER_VAR
+            )
+        })
+    }
+}
 
-    Ok(reader.lines())
+pub fn read_file(file_name: &str) -> Result<InputLines> {
+    InputLocation::from_env().open(file_name)
 }
 
 #[allow(dead_code)]
@@ -57,6 +148,123 @@ fn load_numbers(file_name: &str) -> Result<Vec<i64>> {
     Ok(result)
 }
 
+type RunnerFactory = fn(ArcStr) -> std::result::Result<Box<dyn Runner>, Box<dyn std::error::Error>>;
+
+/// One `#[aoc]` solution, runnable without the cargo-aoc toolchain.
+pub struct Solution {
+    pub day: u8,
+    pub part: u8,
+    pub variant: Option<&'static str>,
+    factory: RunnerFactory,
+}
+
+impl Solution {
+    /// Runs the day's generator over `input`, ready to be solved.
+    pub fn generate(&self, input: &str) -> Result<Box<dyn Runner>> {
+        (self.factory)(ArcStr::from(input)).map_err(|e| anyhow!("Generator failed: {}", e))
+    }
+
+    pub fn run(&self, input: &str) -> Result<String> {
+        let answer = self
+            .generate(input)?
+            .try_run()
+            .map_err(|e| anyhow!("Solver failed: {}", e))?;
+        Ok(answer.to_string())
+    }
+}
+
+impl Display for Solution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Day {} - Part {}", self.day, self.part)?;
+        if let Some(variant) = self.variant {
+            write!(f, " - {}", variant)?;
+        }
+        Ok(())
+    }
+}
+
+fn solution(day: u8, part: u8, variant: Option<&'static str>, factory: RunnerFactory) -> Solution {
+    Solution {
+        day,
+        part,
+        variant,
+        factory,
+    }
+}
+
+/// Every solution in the crate, ordered by day, part and variant.
+pub fn solutions() -> Vec<Solution> {
+    vec![
+        solution(1, 1, None, Factory::day1_part1),
+        solution(1, 2, None, Factory::day1_part2),
+        solution(2, 1, None, Factory::day2_part1),
+        solution(2, 2, None, Factory::day2_part2),
+        solution(3, 1, None, Factory::day3_part1),
+        solution(3, 2, None, Factory::day3_part2),
+        solution(4, 1, None, Factory::day4_part1),
+        solution(4, 2, None, Factory::day4_part2),
+        solution(5, 1, None, Factory::day5_part1),
+        solution(5, 2, None, Factory::day5_part2),
+        solution(6, 1, None, Factory::day6_part1),
+        solution(6, 1, Some("fast"), Factory::day6_part1_fast),
+        solution(6, 2, Some("fast"), Factory::day6_part2_fast),
+        solution(7, 1, None, Factory::day7_part1),
+        solution(7, 2, None, Factory::day7_part2),
+        solution(8, 1, None, Factory::day8_part1),
+        solution(8, 2, None, Factory::day8_part2),
+        solution(9, 1, None, Factory::day9_part1),
+        solution(9, 2, None, Factory::day9_part2),
+        solution(10, 1, None, Factory::day10_part1),
+        solution(10, 2, None, Factory::day10_part2),
+        solution(11, 1, None, Factory::day11_part1),
+        solution(11, 2, None, Factory::day11_part2),
+        solution(12, 1, None, Factory::day12_part1),
+        solution(12, 1, Some("enumerate"), Factory::day12_part1_enumerate),
+        solution(12, 2, None, Factory::day12_part2),
+        solution(12, 2, Some("enumerate"), Factory::day12_part2_enumerate),
+        solution(13, 1, None, Factory::day13_part1),
+        solution(13, 2, None, Factory::day13_part2),
+        solution(14, 1, None, Factory::day14_part1),
+        solution(14, 1, Some("engine"), Factory::day14_part1_engine),
+        solution(14, 1, Some("pairs"), Factory::day14_part1_pairs),
+        solution(14, 2, None, Factory::day14_part2),
+        solution(14, 2, Some("engine"), Factory::day14_part2_engine),
+        solution(15, 1, None, Factory::day15_part1),
+        solution(15, 1, Some("fast"), Factory::day15_part1_fast),
+        solution(15, 1, Some("graph"), Factory::day15_part1_graph),
+        solution(15, 2, Some("fast"), Factory::day15_part2_fast),
+        solution(16, 1, None, Factory::day16_part1),
+        solution(16, 2, None, Factory::day16_part2),
+        solution(16, 2, Some("big"), Factory::day16_part2_big),
+        solution(17, 1, None, Factory::day17_part1),
+        solution(17, 2, None, Factory::day17_part2),
+        solution(18, 1, None, Factory::day18_part1),
+        solution(18, 2, None, Factory::day18_part2),
+        solution(19, 1, None, Factory::day19_part1),
+        solution(19, 2, None, Factory::day19_part2),
+        solution(20, 1, None, Factory::day20_part1),
+        solution(20, 1, Some("window"), Factory::day20_part1_window),
+        solution(20, 2, None, Factory::day20_part2),
+        solution(20, 2, Some("window"), Factory::day20_part2_window),
+        solution(21, 1, None, Factory::day21_part1),
+        solution(21, 2, None, Factory::day21_part2),
+        solution(22, 1, None, Factory::day22_part1),
+        solution(22, 1, Some("shattered"), Factory::day22_part1_shattered),
+        solution(22, 2, Some("shattered"), Factory::day22_part2_shattered),
+        solution(23, 1, None, Factory::day23_part1),
+        solution(23, 1, Some("astar"), Factory::day23_part1_astar),
+        solution(23, 1, Some("replay"), Factory::day23_part1_replay),
+        solution(23, 2, None, Factory::day23_part2),
+        solution(23, 2, Some("astar"), Factory::day23_part2_astar),
+        solution(23, 2, Some("replay"), Factory::day23_part2_replay),
+        solution(24, 1, None, Factory::day24_part1),
+        solution(24, 1, Some("search"), Factory::day24_part1_search),
+        solution(24, 2, None, Factory::day24_part2),
+        solution(24, 2, Some("search"), Factory::day24_part2_search),
+        solution(25, 1, None, Factory::day25_part1),
+    ]
+}
+
 #[aoc_generator(day1)]
 fn day1_generator(input: &str) -> Vec<i32> {
     input.lines().map(|d| d.parse().unwrap()).collect()
@@ -110,125 +318,1357 @@ where
     }
 }
 
+const NEIGHBORS4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const NEIGHBORS8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// A rectangular grid addressed by `(x, y)` with `y` counting rows down from the top.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    wrapping: bool,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+            wrapping: false,
+        }
+    }
+
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self> {
+        let height = rows.len();
+        let width = rows.first().context("Empty grid")?.len();
+        ensure!(width > 0, "Empty grid rows");
+        ensure!(
+            rows.iter().all(|r| r.len() == width),
+            "Grid rows must all have the same width"
+        );
+        Ok(Self {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+            wrapping: false,
+        })
+    }
+
+    /// Parses one row per non-empty line, converting each character with `parse`.
+    pub fn parse(input: &str, mut parse: impl FnMut(char) -> Result<T>) -> Result<Self> {
+        let rows = input
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(|l| l.chars().map(&mut parse).collect())
+            .collect::<Result<Vec<Vec<T>>>>()?;
+        Self::from_rows(rows)
+    }
+
+    /// Makes neighbors and offsets wrap around the edges like a torus.
+    pub fn with_wrapping(mut self, wrapping: bool) -> Self {
+        self.wrapping = wrapping;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn get(&self, (x, y): (usize, usize)) -> Option<&T> {
+        if x < self.width && y < self.height {
+            self.cells.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, (x, y): (usize, usize)) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            self.cells.get_mut(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> + '_ {
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    /// All coordinates in reading order.
+    pub fn coords(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |idx| (idx % width, idx / width))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.coords().zip(self.cells.iter())
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+        self.cells.iter_mut()
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+            wrapping: self.wrapping,
+        }
+    }
+
+    /// Moves `coord` by `delta`, returning `None` when it leaves a non-wrapping grid.
+    pub fn offset(
+        &self,
+        (x, y): (usize, usize),
+        (dx, dy): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let (x, y) = (x as isize + dx, y as isize + dy);
+        if self.wrapping {
+            Some((x.rem_euclid(width) as usize, y.rem_euclid(height) as usize))
+        } else if (0..width).contains(&x) && (0..height).contains(&y) {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    /// The orthogonally adjacent coordinates.
+    pub fn neighbors4(&self, coord: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        NEIGHBORS4
+            .iter()
+            .filter_map(move |delta| self.offset(coord, *delta))
+    }
+
+    /// The orthogonally and diagonally adjacent coordinates.
+    pub fn neighbors8(&self, coord: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        NEIGHBORS8
+            .iter()
+            .filter_map(move |delta| self.offset(coord, *delta))
+    }
+}
+
+impl Grid<u8> {
+    pub fn parse_digits(input: &str) -> Result<Self> {
+        Self::parse(input, |c| {
+            c.to_digit(10)
+                .map(|d| d as u8)
+                .with_context(|| format!("Not a digit: {}", c))
+        })
+    }
+}
+
+impl Grid<char> {
+    pub fn parse_chars(input: &str) -> Result<Self> {
+        Self::parse(input, Ok)
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) out of bounds",
+            x,
+            y
+        );
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) out of bounds",
+            x,
+            y
+        );
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+impl<T> Display for Grid<T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.height {
+            for cell in self.row(y) {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// A sparse black and white image on an infinite plane. Only the points that
+/// differ from the background are stored, so even an infinite lit plane stays small.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bitmap {
+    points: HashSet<(i32, i32)>,
+    background: bool,
+    bounds: Option<((i32, i32), (i32, i32))>,
+}
+
+impl Bitmap {
+    pub fn new(background: bool) -> Self {
+        Self {
+            background,
+            ..Default::default()
+        }
+    }
+
+    pub fn background(&self) -> bool {
+        self.background
+    }
+
+    /// The smallest box `(min, max)` holding every point that differs from the background.
+    pub fn bounds(&self) -> Option<((i32, i32), (i32, i32))> {
+        self.bounds
+    }
+
+    pub fn get(&self, point: &(i32, i32)) -> bool {
+        self.points.contains(point) != self.background
+    }
+
+    pub fn set(&mut self, point: (i32, i32), value: bool) {
+        if value != self.background {
+            if self.points.insert(point) {
+                self.bounds = Some(match self.bounds {
+                    None => (point, point),
+                    Some((min, max)) => (
+                        (min.0.min(point.0), min.1.min(point.1)),
+                        (max.0.max(point.0), max.1.max(point.1)),
+                    ),
+                });
+            }
+        } else if self.points.remove(&point) {
+            if let Some((min, max)) = self.bounds {
+                if point.0 == min.0 || point.0 == max.0 || point.1 == min.1 || point.1 == max.1 {
+                    self.recalculate_bounds();
+                }
+            }
+        }
+    }
+
+    fn recalculate_bounds(&mut self) {
+        self.bounds = self.points.iter().fold(None, |bounds, point| {
+            Some(match bounds {
+                None => (*point, *point),
+                Some((min, max)) => (
+                    (min.0.min(point.0), min.1.min(point.1)),
+                    (max.0.max(point.0), max.1.max(point.1)),
+                ),
+            })
+        });
+    }
+
+    /// The points that differ from the background.
+    pub fn points(&self) -> impl Iterator<Item = &(i32, i32)> + '_ {
+        self.points.iter()
+    }
+
+    /// How many points are lit, or `None` when the lit background makes it infinite.
+    pub fn lit_count(&self) -> Option<usize> {
+        if self.background {
+            None
+        } else {
+            Some(self.points.len())
+        }
+    }
+
+    /// Mirrors the whole plane across the line `axis = line`.
+    pub fn reflect(&self, axis: Axis, line: i32) -> Self {
+        let mut result = Self::new(self.background);
+        for point in &self.points {
+            result.set(reflect_point(*point, axis, line), !self.background);
+        }
+        result
+    }
+
+    /// Folds everything beyond the line `axis = line` back over the near side,
+    /// as if the plane were transparent paper: a point is lit if either layer is.
+    pub fn fold(&self, axis: Axis, line: i32) -> Self {
+        let mut result = Self::new(self.background);
+        for point in &self.points {
+            let beyond = match axis {
+                Axis::X => point.0 > line,
+                Axis::Y => point.1 > line,
+            };
+            let target = if beyond {
+                reflect_point(*point, axis, line)
+            } else {
+                *point
+            };
+            if self.background {
+                // Dark only where both layers are dark
+                let other = reflect_point(target, axis, line);
+                if !self.get(&target) && !self.get(&other) {
+                    result.set(target, false);
+                }
+            } else {
+                result.set(target, true);
+            }
+        }
+        result
+    }
+}
+
+fn reflect_point(point: (i32, i32), axis: Axis, line: i32) -> (i32, i32) {
+    match axis {
+        Axis::X => (line + line - point.0, point.1),
+        Axis::Y => (point.0, line + line - point.1),
+    }
+}
+
+impl FromIterator<(i32, i32)> for Bitmap {
+    /// Builds a bitmap with the given points lit on a dark background.
+    fn from_iter<I: IntoIterator<Item = (i32, i32)>>(iter: I) -> Self {
+        let mut result = Self::new(false);
+        for point in iter {
+            result.set(point, true);
+        }
+        result
+    }
+}
+
+impl Display for Bitmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((min, max)) = self.bounds {
+            for y in min.1..=max.1 {
+                for x in min.0..=max.0 {
+                    write!(f, "{}", if self.get(&(x, y)) { '#' } else { '.' })?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adjacency list graph. Weighted graphs carry a `W` cost on each edge and
+/// may attach an `A` attribute to any node.
 #[derive(Debug, Clone)]
-pub struct Graph<T>
+pub struct Graph<T, W = (), A = ()>
 where
     T: Clone + Eq + Hash,
 {
     digraph: bool,
     map: HashMap<T, Vec<T>>,
+    weights: HashMap<(T, T), W>,
+    attributes: HashMap<T, A>,
 }
 
 impl<T> Graph<T>
 where
     T: Clone + Eq + Hash,
 {
+    /// Creates an empty graph whose edges only go one way when `digraph` is set.
     pub fn new(digraph: bool) -> Self {
+        Self::weighted(digraph)
+    }
+
+    pub fn add_edge(&mut self, from: T, to: T) {
+        self.link(from, to);
+    }
+}
+
+impl<T, W, A> Graph<T, W, A>
+where
+    T: Clone + Eq + Hash,
+    W: Clone,
+    A: Clone,
+{
+    /// Creates an empty graph for [`Graph::add_weighted_edge`].
+    pub fn weighted(digraph: bool) -> Self {
         Self {
             digraph,
             map: HashMap::new(),
+            weights: HashMap::new(),
+            attributes: HashMap::new(),
         }
     }
 
-    pub fn add_edge(&mut self, from: T, to: T) {
+    pub fn is_directed(&self) -> bool {
+        self.digraph
+    }
+
+    pub fn add_node(&mut self, node: T) {
+        self.map.entry(node).or_default();
+    }
+
+    /// Adds an edge costing `weight`. Adding an existing edge again replaces
+    /// its weight instead of duplicating it.
+    pub fn add_weighted_edge(&mut self, from: T, to: T, weight: W) {
+        let key = (from, to);
+        let existing = self.weights.contains_key(&key);
+        if !self.digraph {
+            self.weights
+                .insert((key.1.clone(), key.0.clone()), weight.clone());
+        }
+        self.weights.insert(key.clone(), weight);
+        if !existing {
+            self.link(key.0, key.1);
+        }
+    }
+
+    pub fn edge_weight(&self, from: &T, to: &T) -> Option<&W> {
+        self.weights.get(&(from.clone(), to.clone()))
+    }
+
+    /// The weighted edges leaving `node`.
+    pub fn weighted_edges<'a>(&'a self, node: &'a T) -> impl Iterator<Item = (&'a T, &'a W)> + 'a {
+        self.neighbors(node)
+            .filter_map(move |dest| Some((dest, self.edge_weight(node, dest)?)))
+    }
+
+    /// Attaches `attribute` to `node`, adding the node if needed.
+    pub fn set_attribute(&mut self, node: T, attribute: A) {
+        self.add_node(node.clone());
+        self.attributes.insert(node, attribute);
+    }
+
+    pub fn attribute(&self, node: &T) -> Option<&A> {
+        self.attributes.get(node)
+    }
+
+    fn link(&mut self, from: T, to: T) {
         if self.digraph {
+            self.add_node(to.clone());
+        } else {
             self.map.entry(to.clone()).or_default().push(from.clone());
         }
         self.map.entry(from).or_default().push(to);
     }
 
-    pub fn nodes(&self) -> std::collections::hash_map::Keys<T, Vec<T>> {
+    pub fn nodes(&self) -> std::collections::hash_map::Keys<'_, T, Vec<T>> {
         self.map.keys()
     }
 
-    pub fn edges<Q: ?Sized>(&self, node: &Q) -> Option<&Vec<T>>
+    pub fn edges<Q>(&self, node: &Q) -> Option<&Vec<T>>
     where
         T: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Eq + ?Sized,
     {
         self.map.get(node)
     }
-}
 
-impl<T> MatrixTranspose for Graph<T>
-where
-    T: Clone + Eq + Hash,
-{
-    fn transpose(&self) -> Self {
-        if self.digraph {
-            self.clone()
-        } else {
-            let mut result = Graph::new(self.digraph);
-            for (node, edges) in &self.map {
-                for dest in edges {
-                    result.add_edge(dest.clone(), node.clone());
+    fn neighbors<'a>(&'a self, node: &T) -> impl Iterator<Item = &'a T> + 'a {
+        self.map.get(node).into_iter().flatten()
+    }
+
+    /// Nodes reachable from `start` in breadth first order.
+    pub fn bfs(&self, start: &T) -> Vec<T> {
+        let mut seen = HashSet::new();
+        let mut queue = std::collections::VecDeque::new();
+        let mut result = vec![];
+        seen.insert(start.clone());
+        queue.push_back(start.clone());
+        while let Some(node) = queue.pop_front() {
+            for next in self.neighbors(&node) {
+                if seen.insert(next.clone()) {
+                    queue.push_back(next.clone());
                 }
             }
-            result
+            result.push(node);
         }
+        result
     }
-}
 
-impl<T> Graph<T>
-where
-    T: Ord + Clone + Eq + Hash,
-{
-    pub fn sort(&mut self) {
-        self.map.values_mut().for_each(|v| v.sort_unstable());
+    /// Nodes reachable from `start` in depth first pre-order.
+    pub fn dfs(&self, start: &T) -> Vec<T> {
+        let mut seen = HashSet::new();
+        let mut result = vec![];
+        self.dfs_visit(start, &mut seen, &mut result, &mut vec![]);
+        result
     }
-}
 
-impl<T> Display for Graph<T>
-where
-    T: Clone + Eq + Hash + Display,
-{
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for e in &self.map {
-            write!(f, "{} ->", e.0)?;
-            for v in e.1.iter().enumerate() {
-                if v.0 == 0 {
-                    write!(f, " {}", v.1)?;
-                } else {
-                    write!(f, ", {}", v.1)?;
-                }
-            }
-            writeln!(f)?;
+    fn dfs_visit(&self, node: &T, seen: &mut HashSet<T>, pre: &mut Vec<T>, post: &mut Vec<T>) {
+        if !seen.insert(node.clone()) {
+            return;
         }
-        Ok(())
+        pre.push(node.clone());
+        for next in self.neighbors(node) {
+            self.dfs_visit(next, seen, pre, post);
+        }
+        post.push(node.clone());
     }
-}
 
-pub struct ReplacementEngine<T>
-where
-    T: Hash + Eq + Clone,
-{
-    pub elements: HashMap<T, u64>,
-    pub rules: HashMap<T, Vec<(T, u64)>>,
-}
+    pub fn reachable(&self, start: &T) -> HashSet<T> {
+        self.bfs(start).into_iter().collect()
+    }
 
-impl<T> ReplacementEngine<T>
-where
-    T: Hash + Eq + Clone,
-{
-    pub fn new(elements: HashMap<T, u64>, rules: HashMap<T, Vec<(T, u64)>>) -> Self {
-        Self { elements, rules }
+    pub fn is_reachable(&self, from: &T, to: &T) -> bool {
+        self.bfs(from).contains(to)
     }
 
-    pub fn step(&mut self) {
-        let mut result = HashMap::new();
-        for (node, count) in &self.elements {
-            if let Some(replacements) = self.rules.get(node) {
-                for r in replacements {
-                    *result.entry(r.0.to_owned()).or_default() += *count * r.1;
+    /// Orders the nodes so every edge points forward, or `None` if there is a cycle.
+    pub fn topological_sort(&self) -> Option<Vec<T>> {
+        if !self.digraph {
+            return if self.has_cycle() {
+                None
+            } else {
+                Some(self.map.keys().cloned().collect())
+            };
+        }
+        let mut in_degree: HashMap<&T, usize> = self.map.keys().map(|n| (n, 0)).collect();
+        for dest in self.map.values().flatten() {
+            *in_degree.entry(dest).or_default() += 1;
+        }
+        let mut ready: Vec<&T> = in_degree
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(n, _)| *n)
+            .collect();
+        let mut result = vec![];
+        while let Some(node) = ready.pop() {
+            result.push(node.clone());
+            for next in self.neighbors(node) {
+                let degree = in_degree.get_mut(next).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.push(next);
                 }
             }
         }
-        self.elements = result;
+        if result.len() == self.map.len() {
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    pub fn has_cycle(&self) -> bool {
+        if self.digraph {
+            self.topological_sort().is_none()
+        } else {
+            // A forest has exactly one fewer edge than nodes in each tree
+            let edge_count = self.map.values().map(Vec::len).sum::<usize>() / 2;
+            edge_count + self.connected_components().len() != self.map.len()
+        }
+    }
+
+    /// Groups nodes that are connected ignoring edge direction.
+    pub fn connected_components(&self) -> Vec<Vec<T>> {
+        let mut undirected = Graph::new(true);
+        for (node, edges) in &self.map {
+            undirected.add_node(node.clone());
+            for dest in edges {
+                undirected.add_edge(node.clone(), dest.clone());
+                if self.digraph {
+                    undirected.add_edge(dest.clone(), node.clone());
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        let mut result = vec![];
+        for node in undirected.nodes() {
+            if !seen.contains(node) {
+                let component = undirected.bfs(node);
+                seen.extend(component.iter().cloned());
+                result.push(component);
+            }
+        }
+        result
+    }
+
+    /// Groups nodes that can all reach each other, using Kosaraju's algorithm.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<T>> {
+        let mut seen = HashSet::new();
+        let mut finished = vec![];
+        for node in self.nodes() {
+            self.dfs_visit(node, &mut seen, &mut vec![], &mut finished);
+        }
+
+        let transposed = self.transpose();
+        let mut seen = HashSet::new();
+        let mut result = vec![];
+        for node in finished.iter().rev() {
+            let mut component = vec![];
+            transposed.dfs_visit(node, &mut seen, &mut component, &mut vec![]);
+            if !component.is_empty() {
+                result.push(component);
+            }
+        }
+        result
+    }
+}
+
+impl<T, W, A> Graph<T, W, A>
+where
+    T: Clone + Eq + Hash,
+    W: Copy + Ord + Default + Add<Output = W>,
+    A: Clone,
+{
+    /// The cheapest route from `start` to `goal` following weighted edges.
+    pub fn shortest_path(&self, start: &T, goal: &T) -> Option<ShortestPath<T, W>> {
+        let neighbors = |node: &T| {
+            self.weighted_edges(node)
+                .map(|(dest, weight)| (dest.clone(), *weight))
+                .collect::<Vec<_>>()
+        };
+        dijkstra(start.clone(), neighbors, |node| node == goal)
+    }
+
+    pub fn distance(&self, start: &T, goal: &T) -> Option<W> {
+        self.shortest_path(start, goal).map(|path| path.cost)
+    }
+}
+
+impl<T, W, A> MatrixTranspose for Graph<T, W, A>
+where
+    T: Clone + Eq + Hash,
+    W: Clone,
+    A: Clone,
+{
+    fn transpose(&self) -> Self {
+        if self.digraph {
+            let mut result = Self::weighted(self.digraph);
+            for (node, edges) in &self.map {
+                result.add_node(node.clone());
+                for dest in edges {
+                    result.link(dest.clone(), node.clone());
+                }
+            }
+            result.weights = self
+                .weights
+                .iter()
+                .map(|((from, to), weight)| ((to.clone(), from.clone()), weight.clone()))
+                .collect();
+            result.attributes = self.attributes.clone();
+            result
+        } else {
+            self.clone()
+        }
+    }
+}
+
+fn parse_node<T: FromStr>(name: &str) -> Result<T> {
+    let name = name.trim();
+    ensure!(!name.is_empty(), "Missing node name");
+    name.parse().map_err(|_| anyhow!("Bad node: {}", name))
+}
+
+impl<T> Graph<T>
+where
+    T: Clone + Eq + Hash + FromStr,
+{
+    /// Parses one edge per line, either `a-b` for an undirected graph or
+    /// `a -> b` for a directed one.
+    pub fn parse_edges(input: &str) -> Result<Self> {
+        let mut result: Option<Self> = None;
+        for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (digraph, (from, to)) = match line.split_once("->") {
+                Some(pair) => (true, pair),
+                None => (
+                    false,
+                    line.split_once('-')
+                        .with_context(|| format!("Bad edge: {}", line))?,
+                ),
+            };
+            let graph = result.get_or_insert_with(|| Graph::new(digraph));
+            ensure!(
+                graph.digraph == digraph,
+                "Mixed directed and undirected edges at: {}",
+                line
+            );
+            graph.add_edge(parse_node(from)?, parse_node(to)?);
+        }
+        result.context("No edges")
+    }
+
+    /// Parses the `node -> a, b` lines written by `Display`. Undirected
+    /// graphs must list every edge from both ends.
+    pub fn parse_adjacency(input: &str, digraph: bool) -> Result<Self> {
+        let mut result = Graph::new(digraph);
+        let mut listed = HashSet::new();
+        for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (node, edges) = line
+                .split_once("->")
+                .with_context(|| format!("Bad adjacency line: {}", line))?;
+            let node: T = parse_node(node)?;
+            ensure!(listed.insert(node.clone()), "Node listed twice: {}", line);
+            let mut dests = vec![];
+            for dest in edges.split(',').filter(|d| !d.trim().is_empty()) {
+                let dest: T = parse_node(dest)?;
+                result.add_node(dest.clone());
+                dests.push(dest);
+            }
+            result.map.entry(node).or_default().extend(dests);
+        }
+
+        if !digraph {
+            let mut balance: HashMap<(&T, &T), i32> = HashMap::new();
+            for (node, edges) in &result.map {
+                for dest in edges {
+                    *balance.entry((node, dest)).or_default() += 1;
+                    *balance.entry((dest, node)).or_default() -= 1;
+                }
+            }
+            ensure!(
+                balance.values().all(|b| *b == 0),
+                "Undirected edges must be listed from both ends"
+            );
+        }
+        Ok(result)
+    }
+}
+
+fn dot_quote(name: impl Display) -> String {
+    format!(
+        "\"{}\"",
+        name.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+impl<T, W, A> Graph<T, W, A>
+where
+    T: Ord + Clone + Eq + Hash,
+{
+    pub fn sort(&mut self) {
+        self.map.values_mut().for_each(|v| v.sort_unstable());
+    }
+
+    fn sorted_nodes(&self) -> Vec<&T> {
+        let mut nodes: Vec<&T> = self.map.keys().collect();
+        nodes.sort_unstable();
+        nodes
+    }
+
+    fn sorted_edges(&self, node: &T) -> Vec<&T> {
+        let mut edges: Vec<&T> = self.map.get(node).into_iter().flatten().collect();
+        edges.sort_unstable();
+        edges
+    }
+
+    /// Every edge once in sorted order. Undirected edges are reported from
+    /// their smaller end.
+    fn edge_list(&self) -> Vec<(&T, &T)> {
+        let mut result = vec![];
+        for node in self.sorted_nodes() {
+            let mut skip_loop = false;
+            for dest in self.sorted_edges(node) {
+                if !self.digraph {
+                    if dest < node {
+                        continue;
+                    }
+                    // Undirected self loops are stored twice
+                    if dest == node {
+                        skip_loop = !skip_loop;
+                        if !skip_loop {
+                            continue;
+                        }
+                    }
+                }
+                result.push((node, dest));
+            }
+        }
+        result
+    }
+}
+
+impl<T, W, A> Graph<T, W, A>
+where
+    T: Ord + Clone + Eq + Hash + Display,
+    W: Debug,
+{
+    /// Renders the graph in Graphviz DOT format with nodes and edges sorted.
+    /// The nodes and steps along `highlight` are drawn in red.
+    pub fn to_dot(&self, highlight: Option<&[T]>) -> String {
+        let path = highlight.unwrap_or_default();
+        let on_path = |from: &T, to: &T| {
+            path.windows(2).any(|step| {
+                (&step[0] == from && &step[1] == to)
+                    || (!self.digraph && &step[0] == to && &step[1] == from)
+            })
+        };
+        let (kind, arrow) = if self.digraph {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+
+        let mut result = format!("{} {{\n", kind);
+        for node in self.sorted_nodes() {
+            let style = if path.contains(node) {
+                " [color=red]"
+            } else {
+                ""
+            };
+            result.push_str(&format!("    {}{};\n", dot_quote(node), style));
+        }
+        for (from, to) in self.edge_list() {
+            let mut attributes = vec![];
+            if let Some(weight) = self.weights.get(&(from.clone(), to.clone())) {
+                attributes.push(format!("label={}", dot_quote(format!("{:?}", weight))));
+            }
+            if on_path(from, to) {
+                attributes.push("color=red, penwidth=2".to_string());
+            }
+            let style = if attributes.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", attributes.join(", "))
+            };
+            result.push_str(&format!(
+                "    {} {} {}{};\n",
+                dot_quote(from),
+                arrow,
+                dot_quote(to),
+                style
+            ));
+        }
+        result.push_str("}\n");
+        result
+    }
+}
+
+impl<T, W, A> Display for Graph<T, W, A>
+where
+    T: Ord + Clone + Eq + Hash + Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for node in self.sorted_nodes() {
+            write!(f, "{} ->", node)?;
+            for v in self.sorted_edges(node).iter().enumerate() {
+                if v.0 == 0 {
+                    write!(f, " {}", v.1)?;
+                } else {
+                    write!(f, ", {}", v.1)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A population count for [`ReplacementEngine`] whose arithmetic reports
+/// overflow instead of wrapping.
+pub trait Count: Clone + PartialEq {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+}
+
+macro_rules! primitive_count {
+    ($($t:ty),*) => {
+        $(impl Count for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn one() -> Self {
+                1
+            }
+
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
+
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *other)
+            }
+        })*
+    };
+}
+
+primitive_count!(u32, u64, u128, usize);
+
+impl Count for BigUint {
+    fn zero() -> Self {
+        BigUint::default()
+    }
+
+    fn one() -> Self {
+        BigUint::from(1u8)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+}
+
+/// An integer modulo `M`, for when only the remainder of a huge count matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Modular<const M: u64>(u64);
+
+impl<const M: u64> Modular<M> {
+    pub fn new(value: u64) -> Self {
+        Self(value % M)
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl<const M: u64> Count for Modular<M> {
+    fn zero() -> Self {
+        Self(0)
+    }
+
+    fn one() -> Self {
+        Self::new(1)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(Self(
+            ((self.0 as u128 + other.0 as u128) % M as u128) as u64,
+        ))
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(Self(
+            ((self.0 as u128 * other.0 as u128) % M as u128) as u64,
+        ))
+    }
+}
+
+impl<const M: u64> Display for Modular<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (mod {})", self.0, M)
+    }
+}
+
+/// `acc += a * b`, failing on overflow.
+fn add_product<C: Count>(acc: &mut C, a: &C, b: &C) -> Result<()> {
+    *acc = a
+        .checked_mul(b)
+        .and_then(|product| acc.checked_add(&product))
+        .context("Count overflow")?;
+    Ok(())
+}
+
+pub struct ReplacementEngine<T, C = u64>
+where
+    T: Hash + Eq + Clone,
+    C: Count,
+{
+    pub elements: HashMap<T, C>,
+    pub rules: HashMap<T, Vec<(T, C)>>,
+}
+
+impl<T, C> ReplacementEngine<T, C>
+where
+    T: Hash + Eq + Clone,
+    C: Count,
+{
+    pub fn new(elements: HashMap<T, C>, rules: HashMap<T, Vec<(T, C)>>) -> Self {
+        Self { elements, rules }
+    }
+
+    pub fn step(&mut self) -> Result<()> {
+        let mut result = HashMap::new();
+        for (node, count) in &self.elements {
+            if let Some(replacements) = self.rules.get(node) {
+                for r in replacements {
+                    let entry = result.entry(r.0.to_owned()).or_insert_with(C::zero);
+                    add_product(entry, count, &r.1)?;
+                }
+            }
+        }
+        self.elements = result;
+        Ok(())
+    }
+
+    /// Equivalent to calling [`ReplacementEngine::step`] `steps` times, but
+    /// raises the transition matrix to the `steps` power by repeated squaring
+    /// so it takes O(k³ log steps) for k distinct elements.
+    pub fn advance(&mut self, mut steps: u64) -> Result<()> {
+        // Only elements reachable from the current ones matter
+        let mut index: HashMap<T, usize> = HashMap::new();
+        let mut order: Vec<T> = vec![];
+        let mut pending: Vec<T> = self.elements.keys().cloned().collect();
+        while let Some(element) = pending.pop() {
+            if index.contains_key(&element) {
+                continue;
+            }
+            index.insert(element.clone(), order.len());
+            if let Some(replacements) = self.rules.get(&element) {
+                pending.extend(replacements.iter().map(|r| r.0.clone()));
+            }
+            order.push(element);
+        }
+
+        let size = order.len();
+        let mut transition = vec![vec![C::zero(); size]; size];
+        for (from, element) in order.iter().enumerate() {
+            for (to, count) in self.rules.get(element).into_iter().flatten() {
+                let cell = &mut transition[index[to]][from];
+                *cell = cell.checked_add(count).context("Count overflow")?;
+            }
+        }
+
+        let mut counts = vec![C::zero(); size];
+        for (element, count) in &self.elements {
+            counts[index[element]] = count.clone();
+        }
+        while steps > 0 {
+            if steps & 1 == 1 {
+                counts = matrix_apply(&transition, &counts)?;
+            }
+            steps >>= 1;
+            if steps == 0 {
+                // The next power is never used, and may overflow when the
+                // result does not
+                break;
+            }
+            transition = matrix_square(&transition)?;
+        }
+
+        self.elements = order
+            .into_iter()
+            .zip(counts)
+            .filter(|(_, count)| !count.is_zero())
+            .collect();
+        Ok(())
+    }
+
+    /// The sum of all element counts.
+    pub fn total(&self) -> Result<C> {
+        let mut result = C::zero();
+        for count in self.elements.values() {
+            result = result.checked_add(count).context("Count overflow")?;
+        }
+        Ok(result)
+    }
+}
+
+fn matrix_apply<C: Count>(matrix: &[Vec<C>], vector: &[C]) -> Result<Vec<C>> {
+    let mut result = vec![];
+    for row in matrix {
+        let mut sum = C::zero();
+        for (a, b) in row.iter().zip(vector) {
+            add_product(&mut sum, a, b)?;
+        }
+        result.push(sum);
+    }
+    Ok(result)
+}
+
+fn matrix_square<C: Count>(matrix: &[Vec<C>]) -> Result<Vec<Vec<C>>> {
+    let size = matrix.len();
+    let mut result = vec![vec![C::zero(); size]; size];
+    for (row, out) in matrix.iter().zip(&mut result) {
+        for (a, other) in row.iter().zip(matrix) {
+            if a.is_zero() {
+                continue;
+            }
+            for (cell, b) in out.iter_mut().zip(other) {
+                add_product(cell, a, b)?;
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// The cheapest route found by [`astar`] or [`dijkstra`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortestPath<N, C> {
+    pub cost: C,
+    /// Every node from start to goal with the total cost to reach it.
+    pub path: Vec<(N, C)>,
+    /// How many nodes had their neighbors explored.
+    pub expanded: usize,
+}
+
+/// A* search from `start` until a node satisfying `goal` is reached.
+/// `neighbors` yields each reachable node with the cost of the step to it and
+/// `heuristic` must never overestimate the remaining cost.
+pub fn astar<N, C, I>(
+    start: N,
+    mut neighbors: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Option<ShortestPath<N, C>>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut queue = BinaryHeap::new();
+    let mut index = HashMap::new();
+    let mut nodes = vec![start.clone()];
+    let mut best = vec![C::default()];
+    let mut parents = vec![None];
+    let mut closed = vec![false];
+    let mut expanded = 0;
+    index.insert(start, 0);
+    queue.push(Reverse((heuristic(&nodes[0]), C::default(), 0)));
+    while let Some(Reverse((_, cost, idx))) = queue.pop() {
+        if closed[idx] || cost > best[idx] {
+            continue;
+        }
+        closed[idx] = true;
+        if goal(&nodes[idx]) {
+            let mut path = vec![];
+            let mut current = Some(idx);
+            while let Some(idx) = current {
+                path.push((nodes[idx].clone(), best[idx]));
+                current = parents[idx];
+            }
+            path.reverse();
+            return Some(ShortestPath {
+                cost,
+                path,
+                expanded,
+            });
+        }
+        expanded += 1;
+        for (next, step) in neighbors(&nodes[idx]) {
+            let next_cost = cost + step;
+            let next_idx = if let Some(next_idx) = index.get(&next) {
+                if next_cost >= best[*next_idx] {
+                    continue;
+                }
+                best[*next_idx] = next_cost;
+                parents[*next_idx] = Some(idx);
+                closed[*next_idx] = false;
+                *next_idx
+            } else {
+                nodes.push(next.clone());
+                best.push(next_cost);
+                parents.push(Some(idx));
+                closed.push(false);
+                index.insert(next, nodes.len() - 1);
+                nodes.len() - 1
+            };
+            let priority = next_cost + heuristic(&nodes[next_idx]);
+            queue.push(Reverse((priority, next_cost, next_idx)));
+        }
+    }
+    None
+}
+
+/// [`astar`] without a heuristic.
+pub fn dijkstra<N, C, I>(
+    start: N,
+    neighbors: impl FnMut(&N) -> I,
+    goal: impl FnMut(&N) -> bool,
+) -> Option<ShortestPath<N, C>>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(start, neighbors, |_| C::default(), goal)
+}
+
+/// Differential testing of alternate solvers for the same part.
+#[cfg(test)]
+pub(crate) mod differential {
+    use std::fmt::Debug;
+
+    use anyhow::Result;
+
+    /// Xorshift generator, seeded so a failing case can be reproduced.
+    pub struct Rng(u64);
+
+    impl Rng {
+        pub fn new(seed: u64) -> Self {
+            Self(seed.max(1))
+        }
+
+        pub fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// A value in `low..=high`.
+        pub fn range(&mut self, low: i64, high: i64) -> i64 {
+            low + (self.next_u64() % (high - low + 1) as u64) as i64
+        }
+
+        pub fn chance(&mut self, percent: u64) -> bool {
+            self.next_u64() % 100 < percent
+        }
+
+        pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+            items[self.range(0, items.len() as i64 - 1) as usize]
+        }
+    }
+
+    pub type Solver<'a> = (&'a str, &'a dyn Fn(&str) -> Result<String>);
+
+    pub fn solver<'a>(name: &'a str, solve: &'a dyn Fn(&str) -> Result<String>) -> Solver<'a> {
+        (name, solve)
+    }
+
+    /// Every copy of `items` with one element removed, for use as `shrink`.
+    pub fn without_each<T: Clone>(items: &[T]) -> Vec<Vec<T>> {
+        (0..items.len())
+            .map(|i| {
+                let mut result = items.to_vec();
+                result.remove(i);
+                result
+            })
+            .collect()
+    }
+
+    fn answers(solvers: &[Solver], input: &str) -> Vec<Option<String>> {
+        solvers.iter().map(|(_, solve)| solve(input).ok()).collect()
+    }
+
+    fn disagree(answers: &[Option<String>]) -> bool {
+        answers.windows(2).any(|w| w[0] != w[1])
+    }
+
+    /// Runs all `solvers` on `cases` inputs from `generate` and panics if they
+    /// give different answers. The failing input is first shrunk by taking
+    /// smaller candidates from `shrink` for as long as the solvers still
+    /// disagree, so the report shows a minimal case.
+    pub fn check_agreement<I: Debug>(
+        cases: usize,
+        generate: impl Fn(&mut Rng) -> I,
+        shrink: impl Fn(&I) -> Vec<I>,
+        render: impl Fn(&I) -> String,
+        solvers: &[Solver],
+    ) {
+        let mut rng = Rng::new(0x5eed);
+        for _ in 0..cases {
+            let mut input = generate(&mut rng);
+            if !disagree(&answers(solvers, &render(&input))) {
+                continue;
+            }
+            while let Some(smaller) = shrink(&input)
+                .into_iter()
+                .find(|candidate| disagree(&answers(solvers, &render(candidate))))
+            {
+                input = smaller;
+            }
+            let text = render(&input);
+            let report: Vec<String> = solvers
+                .iter()
+                .zip(answers(solvers, &text))
+                .map(|((name, _), answer)| format!("{}: {:?}", name, answer))
+                .collect();
+            panic!(
+                "Solvers disagree on\n{}\n{}\n({:?})",
+                text,
+                report.join("\n"),
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn shrinks_to_minimal_case() {
+        let sum: &dyn Fn(&str) -> Result<String> = &|s| {
+            Ok(s.split(',')
+                .map(|n| n.parse::<i64>().unwrap())
+                .sum::<i64>()
+                .to_string())
+        };
+        // Wrong whenever a 7 is present
+        let buggy: &dyn Fn(&str) -> Result<String> = &|s| {
+            let total: i64 = s
+                .split(',')
+                .map(|n| n.parse::<i64>().unwrap())
+                .filter(|n| *n != 7)
+                .sum();
+            Ok(total.to_string())
+        };
+        let render = |items: &Vec<i64>| {
+            items
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        check_agreement(
+            20,
+            |rng| (0..5).map(|_| rng.range(0, 9)).collect::<Vec<_>>(),
+            |items| {
+                without_each(items)
+                    .into_iter()
+                    .filter(|i| !i.is_empty())
+                    .collect()
+            },
+            render,
+            &[("sum", sum), ("sum", sum)],
+        );
+        let failure = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            check_agreement(
+                20,
+                |_| vec![1, 7, 3, 7],
+                |items| {
+                    without_each(items)
+                        .into_iter()
+                        .filter(|i| !i.is_empty())
+                        .collect()
+                },
+                render,
+                &[("sum", sum), ("buggy", buggy)],
+            )
+        }))
+        .unwrap_err();
+        let message = failure.downcast_ref::<String>().unwrap();
+        assert!(
+            message.starts_with("Solvers disagree on\n7\nsum: Some(\"7\")\nbuggy: Some(\"0\")"),
+            "{}",
+            message
+        );
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use itertools::Itertools;
+
     use super::*;
 
     #[test]
@@ -261,6 +1701,477 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn input_location() -> Result<()> {
+        let location = InputLocation::new("res", None);
+        assert_eq!(
+            PathBuf::from("res").join("day1.txt"),
+            location.path("day1.txt")
+        );
+        assert!(location.read_day(1)?.starts_with("173\n"));
+
+        let alice = location.clone().with_user("alice");
+        assert_eq!(
+            PathBuf::from("res").join("alice").join("day1.txt"),
+            alice.path("day1.txt")
+        );
+        let error = format!("{:#}", alice.read_day(1).unwrap_err());
+        assert!(error.contains("day 1"), "{}", error);
+        assert!(error.contains(&alice.path("day1.txt").display().to_string()));
+
+        let elsewhere = location.with_dir("nowhere");
+        assert_eq!(
+            PathBuf::from("nowhere").join("day7.txt"),
+            elsewhere.path("day7.txt")
+        );
+        assert!(elsewhere.open("day7.txt").is_err());
+        Ok(())
+    }
+
+    /// A line of `answers.txt` next to the puzzle inputs.
+    struct ExpectedAnswer {
+        day: u8,
+        part: u8,
+        variant: String,
+        answer: String,
+    }
+
+    impl ExpectedAnswer {
+        fn matches(&self, solution: &Solution) -> bool {
+            self.day == solution.day
+                && self.part == solution.part
+                && match self.variant.as_str() {
+                    "*" => true,
+                    "-" => solution.variant.is_none(),
+                    variant => solution.variant == Some(variant),
+                }
+        }
+    }
+
+    impl FromStr for ExpectedAnswer {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let fields: Vec<&str> = s.split_whitespace().collect();
+            ensure!(fields.len() == 4, "Bad answer line: {}", s);
+            Ok(Self {
+                day: fields[0].parse().context("Bad day")?,
+                part: fields[1].parse().context("Bad part")?,
+                variant: fields[2].to_owned(),
+                answer: fields[3].to_owned(),
+            })
+        }
+    }
+
+    /// Runs every solution with an expected answer whose input is present.
+    #[test]
+    fn answers() -> Result<()> {
+        let location = InputLocation::from_env();
+        let manifest = location.path("answers.txt");
+        if !manifest.exists() {
+            println!("No {}, skipping", manifest.display());
+            return Ok(());
+        }
+        let expected: Vec<ExpectedAnswer> = location
+            .open("answers.txt")?
+            .map(|line| line.map_err(anyhow::Error::from))
+            .filter(|line| {
+                line.as_ref()
+                    .map_or(true, |l| !l.trim().is_empty() && !l.starts_with('#'))
+            })
+            .map(|line| line?.parse())
+            .collect::<Result<_>>()?;
+        for answer in &expected {
+            ensure!(
+                solutions().iter().any(|s| answer.matches(s)),
+                "No solution for answer {} {} {}",
+                answer.day,
+                answer.part,
+                answer.variant
+            );
+        }
+
+        let mut failures = vec![];
+        for solution in solutions() {
+            let answer = match expected.iter().find(|a| a.matches(&solution)) {
+                Some(answer) => answer,
+                None => continue,
+            };
+            if !location.path(&format!("day{}.txt", solution.day)).exists() {
+                println!("{}: no input, skipping", solution);
+                continue;
+            }
+            let actual = solution
+                .run(&location.read_day(solution.day)?)
+                .unwrap_or_else(|e| format!("error {:#}", e));
+            if actual != answer.answer {
+                failures.push(format!(
+                    "{}: expected {} but got {}",
+                    solution, answer.answer, actual
+                ));
+            }
+        }
+        assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+        Ok(())
+    }
+
+    /// Every `#[aoc(dayN, partM[, variant])]` attribute in the sources.
+    fn aoc_attributes() -> Result<Vec<(u8, u8, Option<String>)>> {
+        let mut result = vec![];
+        for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/src"))? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|e| e != "rs") {
+                continue;
+            }
+            for line in std::fs::read_to_string(&path)?.lines() {
+                let args = match line.trim().strip_prefix("#[aoc(") {
+                    Some(args) => args.trim_end_matches(")]"),
+                    None => continue,
+                };
+                let args: Vec<&str> = args.split(',').map(str::trim).collect();
+                let field = |idx: usize, prefix: &str| -> Result<u8> {
+                    args.get(idx)
+                        .and_then(|a| a.strip_prefix(prefix))
+                        .with_context(|| format!("Bad attribute in {}: {}", path.display(), line))?
+                        .parse()
+                        .with_context(|| format!("Bad attribute in {}: {}", path.display(), line))
+                };
+                result.push((
+                    field(0, "day")?,
+                    field(1, "part")?,
+                    args.get(2).map(|v| v.to_string()),
+                ));
+            }
+        }
+        result.sort();
+        Ok(result)
+    }
+
+    #[test]
+    fn registry() -> Result<()> {
+        let all = solutions();
+        let keys: Vec<_> = all.iter().map(|s| (s.day, s.part, s.variant)).collect();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert!((1..=25).all(|day| keys.iter().any(|k| k.0 == day)));
+        let registered: Vec<_> = keys
+            .iter()
+            .map(|(day, part, variant)| (*day, *part, variant.map(str::to_owned)))
+            .collect();
+        assert_eq!(aoc_attributes()?, registered);
+
+        let fast = all
+            .iter()
+            .find(|s| (s.day, s.part, s.variant) == (6, 2, Some("fast")))
+            .unwrap();
+        assert_eq!("Day 6 - Part 2 - fast", fast.to_string());
+        assert_eq!("26984457539", fast.run("3,4,3,1,2\n")?);
+        assert!(fast.run("x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn grid() -> Result<()> {
+        let grid = Grid::parse_digits("123\n456")?;
+        assert_eq!((3, 2), (grid.width(), grid.height()));
+        assert_eq!(6, grid[(2, 1)]);
+        assert_eq!(None, grid.get((3, 0)));
+        assert_eq!(&[4, 5, 6], grid.row(1));
+        assert_eq!(vec![&2, &5], grid.column(1).collect::<Vec<_>>());
+        assert_eq!("123\n456\n", grid.to_string());
+        assert_eq!(
+            vec![(1, 0), (2, 1)],
+            grid.neighbors4((2, 0)).collect::<Vec<_>>()
+        );
+        assert_eq!(5, grid.neighbors8((1, 0)).count());
+
+        let grid = grid.with_wrapping(true);
+        assert_eq!(Some((0, 1)), grid.offset((2, 0), (1, -1)));
+        assert_eq!(4, grid.neighbors4((0, 0)).count());
+        assert!(Grid::parse_digits("12\n3").is_err());
+        assert!(Grid::parse_digits("1a").is_err());
+        assert!(Grid::<u8>::from_rows(vec![]).is_err());
+        assert!(Grid::<u8>::from_rows(vec![vec![], vec![]]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn bitmap() {
+        let mut bitmap: Bitmap = [(0, 0), (3, 1), (4, 2)].into_iter().collect();
+        assert_eq!(Some(((0, 0), (4, 2))), bitmap.bounds());
+        assert_eq!("#....\n...#.\n....#\n", bitmap.to_string());
+        bitmap.set((4, 2), false);
+        assert_eq!(Some(((0, 0), (3, 1))), bitmap.bounds());
+        assert_eq!(Some(2), bitmap.lit_count());
+
+        let folded = bitmap.fold(Axis::X, 2);
+        assert_eq!("#.\n.#\n", folded.to_string());
+        assert_eq!(
+            vec![(-3, -1), (0, 0)],
+            bitmap
+                .reflect(Axis::Y, 0)
+                .reflect(Axis::X, 0)
+                .points()
+                .copied()
+                .sorted()
+                .collect::<Vec<_>>()
+        );
+
+        let mut inverted = Bitmap::new(true);
+        inverted.set((1, 1), false);
+        inverted.set((3, 1), false);
+        inverted.set((0, 0), false);
+        assert!(inverted.get(&(100, -100)));
+        assert_eq!(None, inverted.lit_count());
+        let folded = inverted.fold(Axis::X, 2);
+        assert!(!folded.get(&(1, 1)));
+        assert!(folded.get(&(0, 0)));
+    }
+
+    #[test]
+    fn graph_direction() {
+        let mut directed = Graph::new(true);
+        directed.add_edge("a", "b");
+        assert_eq!(Some(&vec!["b"]), directed.edges("a"));
+        assert_eq!(Some(&vec![]), directed.edges("b"));
+        assert_eq!(Some(&vec!["a"]), directed.transpose().edges("b"));
+        assert_eq!(Some(&vec![]), directed.transpose().edges("a"));
+
+        let mut undirected = Graph::new(false);
+        undirected.add_edge("a", "b");
+        assert_eq!(Some(&vec!["b"]), undirected.edges("a"));
+        assert_eq!(Some(&vec!["a"]), undirected.edges("b"));
+        assert_eq!(Some(&vec!["a"]), undirected.transpose().edges("b"));
+    }
+
+    #[test]
+    fn graph_algorithms() {
+        let mut graph = Graph::new(true);
+        for (from, to) in [(1, 2), (1, 3), (2, 4), (3, 4), (5, 6)] {
+            graph.add_edge(from, to);
+        }
+        graph.sort();
+        assert_eq!(vec![1, 2, 3, 4], graph.bfs(&1));
+        assert_eq!(vec![1, 2, 4, 3], graph.dfs(&1));
+        assert!(graph.is_reachable(&1, &4));
+        assert!(!graph.is_reachable(&4, &1));
+        assert_eq!(HashSet::from([3, 4]), graph.reachable(&3));
+
+        let order = graph.topological_sort().unwrap();
+        let position = |n| order.iter().position(|o| *o == n).unwrap();
+        assert!(position(1) < position(2) && position(2) < position(4));
+        assert!(position(5) < position(6));
+        assert!(!graph.has_cycle());
+
+        let components: Vec<Vec<i32>> = graph
+            .connected_components()
+            .into_iter()
+            .map(|c| c.into_iter().sorted().collect())
+            .sorted()
+            .collect();
+        assert_eq!(vec![vec![1, 2, 3, 4], vec![5, 6]], components);
+
+        graph.add_edge(4, 1);
+        assert!(graph.has_cycle());
+        assert!(graph.topological_sort().is_none());
+        let components: Vec<Vec<i32>> = graph
+            .strongly_connected_components()
+            .into_iter()
+            .map(|c| c.into_iter().sorted().collect())
+            .sorted()
+            .collect();
+        assert_eq!(vec![vec![1, 2, 3, 4], vec![5], vec![6]], components);
+
+        let mut tree = Graph::new(false);
+        tree.add_edge('a', 'b');
+        tree.add_edge('b', 'c');
+        tree.add_edge('d', 'e');
+        assert!(!tree.has_cycle());
+        assert_eq!(2, tree.strongly_connected_components().len());
+        tree.add_edge('c', 'a');
+        assert!(tree.has_cycle());
+    }
+
+    #[test]
+    fn weighted_graph() {
+        let mut graph: Graph<char, u32, &str> = Graph::weighted(true);
+        graph.add_weighted_edge('a', 'b', 1);
+        graph.add_weighted_edge('b', 'c', 2);
+        graph.add_weighted_edge('a', 'c', 5);
+        graph.add_weighted_edge('c', 'd', 1);
+        graph.set_attribute('d', "exit");
+        assert_eq!(Some(&vec!['b', 'c']), graph.edges(&'a'));
+        assert_eq!(
+            vec![(&'b', &1), (&'c', &5)],
+            graph.weighted_edges(&'a').collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&"exit"), graph.attribute(&'d'));
+        assert_eq!(None, graph.attribute(&'a'));
+
+        let path = graph.shortest_path(&'a', &'d').unwrap();
+        assert_eq!(4, path.cost);
+        assert_eq!(
+            vec!['a', 'b', 'c', 'd'],
+            path.path.iter().map(|p| p.0).collect::<Vec<_>>()
+        );
+        assert_eq!(None, graph.distance(&'d', &'a'));
+        assert_eq!(Some(4), graph.transpose().distance(&'d', &'a'));
+
+        graph.add_weighted_edge('a', 'c', 2);
+        assert_eq!(Some(&vec!['b', 'c']), graph.edges(&'a'));
+        assert_eq!(Some(3), graph.distance(&'a', &'d'));
+
+        let mut undirected: Graph<&str, i64> = Graph::weighted(false);
+        undirected.add_weighted_edge("x", "y", 7);
+        assert_eq!(Some(&7), undirected.edge_weight(&"y", &"x"));
+        assert_eq!(Some(7), undirected.distance(&"y", &"x"));
+    }
+
+    #[test]
+    fn graph_text() -> Result<()> {
+        let graph: Graph<String> = Graph::parse_edges("b-a\nc-a\na-a\nd-d")?;
+        assert!(!graph.is_directed());
+        let text = graph.to_string();
+        assert_eq!("a -> a, a, b, c\nb -> a\nc -> a\nd -> d, d\n", text);
+        let parsed: Graph<String> = Graph::parse_adjacency(&text, false)?;
+        assert_eq!(text, parsed.to_string());
+        assert!(Graph::<String>::parse_adjacency("a -> b", false).is_err());
+        assert!(Graph::<String>::parse_adjacency("a -> b\na -> c", true).is_err());
+
+        let path = ["c".to_string(), "a".to_string(), "b".to_string()];
+        assert_eq!(
+            "graph {
+    \"a\" [color=red];
+    \"b\" [color=red];
+    \"c\" [color=red];
+    \"d\";
+    \"a\" -- \"a\";
+    \"a\" -- \"b\" [color=red, penwidth=2];
+    \"a\" -- \"c\" [color=red, penwidth=2];
+    \"d\" -- \"d\";
+}
+",
+            graph.to_dot(Some(&path))
+        );
+
+        let directed: Graph<u8> = Graph::parse_edges("3 -> 1\n1 -> 2\n2 -> 1")?;
+        assert!(directed.is_directed());
+        assert_eq!("1 -> 2\n2 -> 1\n3 -> 1\n", directed.to_string());
+        let parsed: Graph<u8> = Graph::parse_adjacency(&directed.to_string(), true)?;
+        assert_eq!(directed.to_string(), parsed.to_string());
+        assert!(Graph::<u8>::parse_edges("1 -> 2\n2-3").is_err());
+        assert!(Graph::<u8>::parse_edges("1 -> x").is_err());
+        assert!(Graph::<u8>::parse_edges("12").is_err());
+
+        let mut weighted: Graph<&str, u32> = Graph::weighted(true);
+        weighted.add_weighted_edge("x", "y\"", 3);
+        assert_eq!(
+            "digraph {\n    \"x\";\n    \"y\\\"\";\n    \"x\" -> \"y\\\"\" [label=\"3\"];\n}\n",
+            weighted.to_dot(None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn replacement_engine() -> Result<()> {
+        let rules: HashMap<char, Vec<(char, u64)>> = HashMap::from([
+            ('a', vec![('a', 1), ('b', 1)]),
+            ('b', vec![('a', 1)]),
+            ('c', vec![('c', 2)]),
+        ]);
+        let elements = HashMap::from([('a', 1), ('c', 1)]);
+        let mut stepped = ReplacementEngine::new(elements.clone(), rules.clone());
+        for steps in 1..=20 {
+            stepped.step()?;
+            let mut advanced = ReplacementEngine::new(elements.clone(), rules.clone());
+            advanced.advance(steps)?;
+            assert_eq!(stepped.elements, advanced.elements);
+        }
+        // Fibonacci numbers and powers of two
+        assert_eq!(Some(&10946), stepped.elements.get(&'a'));
+        assert_eq!(Some(&6765), stepped.elements.get(&'b'));
+        assert_eq!(Some(&(1 << 20)), stepped.elements.get(&'c'));
+        assert_eq!(10946 + 6765 + (1 << 20), stepped.total()?);
+
+        let mut engine = ReplacementEngine::new(HashMap::from([('b', 1)]), rules);
+        engine.advance(0)?;
+        assert_eq!(HashMap::from([('b', 1)]), engine.elements);
+        engine.advance(90)?;
+        assert_eq!(Some(&2880067194370816120), engine.elements.get(&'a'));
+
+        let mut dead_end = ReplacementEngine::new(HashMap::from([('x', 5u64)]), HashMap::new());
+        dead_end.advance(3)?;
+        assert!(dead_end.elements.is_empty());
+        Ok(())
+    }
+
+    fn fibonacci<C: Count>(one: C, steps: u64) -> Result<C> {
+        let rules = HashMap::from([
+            ('a', vec![('a', one.clone()), ('b', one.clone())]),
+            ('b', vec![('a', one.clone())]),
+        ]);
+        let mut engine = ReplacementEngine::new(HashMap::from([('b', one)]), rules);
+        engine.advance(steps)?;
+        Ok(engine.elements.get(&'a').cloned().unwrap_or_else(C::zero))
+    }
+
+    #[test]
+    fn replacement_counts() -> Result<()> {
+        assert!(fibonacci(1u64, 100).is_err());
+        // Both fit in a u64, but the next power of the transition matrix
+        // does not
+        assert_eq!(12200160415121876738, fibonacci(1u64, 93)?);
+        let mut doubling = ReplacementEngine::new(
+            HashMap::from([('a', 1u64)]),
+            HashMap::from([('a', vec![('a', 2)])]),
+        );
+        doubling.advance(63)?;
+        assert_eq!(Some(&(1 << 63)), doubling.elements.get(&'a'));
+        assert!(doubling.advance(1).is_err());
+        assert_eq!(354224848179261915075, fibonacci(1u128, 100)?);
+        assert_eq!(
+            "222232244629420445529739893461909967206666939096499764990979600",
+            fibonacci(BigUint::from(1u8), 300)?.to_string()
+        );
+        assert_eq!(
+            517691607,
+            fibonacci(Modular::<1_000_000_007>::new(1), 1000)?.value()
+        );
+        assert_eq!(Modular::<97>::new(72), fibonacci(Modular::new(1), 1000)?);
+
+        let mut stepped = ReplacementEngine::new(
+            HashMap::from([('a', 1u32 << 30)]),
+            HashMap::from([('a', vec![('a', 4)])]),
+        );
+        assert!(stepped.step().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn shortest_path() {
+        let edges: HashMap<char, Vec<(char, u32)>> = [
+            ('a', vec![('b', 7), ('c', 2)]),
+            ('b', vec![('d', 1)]),
+            ('c', vec![('b', 3), ('d', 8)]),
+            ('d', vec![]),
+        ]
+        .into_iter()
+        .collect();
+        let neighbors = |n: &char| edges[n].clone();
+
+        let result = dijkstra('a', neighbors, |n| *n == 'd').unwrap();
+        assert_eq!(6, result.cost);
+        assert_eq!(vec![('a', 0), ('c', 2), ('b', 5), ('d', 6)], result.path);
+
+        let result = astar(
+            'a',
+            neighbors,
+            |n| if *n == 'd' { 0 } else { 1 },
+            |n| *n == 'd',
+        );
+        assert_eq!(6, result.unwrap().cost);
+        assert!(dijkstra('d', neighbors, |n| *n == 'a').is_none());
+    }
 }
 
 aoc_lib! { year = 2021 }